@@ -0,0 +1,545 @@
+// ============================================================================
+// 内置操作：直接处理重复文件（不再需要生成脚本后手动执行）
+// 每组保留一个文件，处理其余文件；处理前再次确认内容与保留的文件完全相同
+// ============================================================================
+
+use crate::journal::{AfterState, FileState, Journal, JournalEntry};
+use crate::quarantine::{self, Manifest, ManifestEntry};
+use crate::trash;
+use crate::{byte_compare, format_size, removable_files, FileInfo};
+use chrono::Local;
+use clap::ValueEnum;
+use colored::*;
+use same_file::is_same_file;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 用链接替换重复文件的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LinkKind {
+    /// 硬链接（必须位于同一文件系统）
+    Hard,
+    /// reflink 写时复制克隆（btrfs / XFS 等支持的文件系统），文件仍可独立修改
+    Reflink,
+    /// 符号链接（可以跨文件系统）
+    Symbolic,
+}
+
+/// 对重复文件执行的操作
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// 直接删除
+    Delete,
+    /// 用指向保留文件的链接替换；`relative` 只对符号链接有效，表示使用相对路径
+    Link { kind: LinkKind, relative: bool },
+    /// 移动到隔离目录（保留原始目录结构，可用清单恢复）
+    MoveTo(PathBuf),
+    /// 移到桌面回收站（freedesktop.org 规范）
+    Trash,
+}
+
+/// 操作对磁盘空间的影响，分开统计
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SpaceEffect {
+    /// 删除文件释放空间
+    Freed,
+    /// 链接共享数据回收空间
+    Reclaimed,
+    /// 移出到隔离目录或回收站（清空后才会释放）
+    Moved,
+}
+
+impl SpaceEffect {
+    fn label(self) -> &'static str {
+        match self {
+            SpaceEffect::Freed => "释放",
+            SpaceEffect::Reclaimed => "回收",
+            SpaceEffect::Moved => "移出",
+        }
+    }
+}
+
+impl Action {
+    /// 输出中使用的动词（演练模式下为"将…"）
+    fn verb(&self, dry_run: bool) -> &'static str {
+        match (self, dry_run) {
+            (Action::Delete, false) => "已删除",
+            (Action::Delete, true) => "将删除",
+            (Action::Link { kind: LinkKind::Hard, .. }, false) => "已硬链接",
+            (Action::Link { kind: LinkKind::Hard, .. }, true) => "将硬链接",
+            (Action::Link { kind: LinkKind::Reflink, .. }, false) => "已克隆",
+            (Action::Link { kind: LinkKind::Reflink, .. }, true) => "将克隆",
+            (Action::Link { kind: LinkKind::Symbolic, .. }, false) => "已符号链接",
+            (Action::Link { kind: LinkKind::Symbolic, .. }, true) => "将符号链接",
+            (Action::MoveTo(_), false) => "已隔离",
+            (Action::MoveTo(_), true) => "将隔离",
+            (Action::Trash, false) => "已移到回收站",
+            (Action::Trash, true) => "将移到回收站",
+        }
+    }
+
+    /// 操作的简短描述（用于确认提示）
+    pub fn describe(&self) -> String {
+        match self {
+            Action::Delete => "删除".to_string(),
+            Action::Link { kind: LinkKind::Hard, .. } => "硬链接".to_string(),
+            Action::Link { kind: LinkKind::Reflink, .. } => "reflink 克隆".to_string(),
+            Action::Link { kind: LinkKind::Symbolic, .. } => "符号链接".to_string(),
+            Action::MoveTo(dir) => format!("移动到 {}", dir.display()),
+            Action::Trash => "移到回收站".to_string(),
+        }
+    }
+
+    /// 扫描结果中预计空间一行的说明：删除释放和链接回收分开显示
+    pub fn space_estimate_label(&self) -> &'static str {
+        match self.space_effect() {
+            SpaceEffect::Freed => "删除可释放空间",
+            SpaceEffect::Reclaimed => "链接可回收空间",
+            SpaceEffect::Moved => "可移出空间",
+        }
+    }
+
+    fn space_effect(&self) -> SpaceEffect {
+        match self {
+            Action::Delete => SpaceEffect::Freed,
+            Action::Link { .. } => SpaceEffect::Reclaimed,
+            Action::MoveTo(_) | Action::Trash => SpaceEffect::Moved,
+        }
+    }
+
+    /// 演练模式：不修改文件，只做执行时会做的元数据检查，返回将释放的字节数
+    fn preview(&self, keep: &FileInfo, target: &FileInfo) -> io::Result<u64> {
+        if let Action::Link { kind: LinkKind::Hard, .. } = self {
+            if !is_same_file(&keep.path, &target.path).unwrap_or(false) {
+                check_same_filesystem(&keep.path, &target.path)?;
+            }
+        }
+        Ok(reclaimable_bytes(keep, target))
+    }
+
+    /// 对单个文件执行操作，返回释放的字节数和操作后的状态（没有修改文件时为 None）
+    fn apply(&self, keep: &FileInfo, target: &FileInfo) -> io::Result<(u64, Option<AfterState>)> {
+        match self {
+            Action::Delete => {
+                let freed = reclaimable_bytes(keep, target);
+                fs::remove_file(&target.path)?;
+                Ok((freed, Some(AfterState::Delete)))
+            }
+            Action::Link { kind: LinkKind::Hard, .. } => {
+                // 已经是同一个文件，无需处理
+                if is_same_file(&keep.path, &target.path).unwrap_or(false) {
+                    return Ok((0, None));
+                }
+                check_same_filesystem(&keep.path, &target.path)?;
+                let freed = reclaimable_bytes(keep, target);
+                replace_with(&target.path, |tmp| fs::hard_link(&keep.path, tmp))?;
+                Ok((freed, Some(AfterState::HardLink { target: keep.path.clone() })))
+            }
+            Action::Link { kind: LinkKind::Reflink, .. } => {
+                if is_same_file(&keep.path, &target.path).unwrap_or(false) {
+                    return Ok((0, None));
+                }
+                let metadata = fs::metadata(&target.path)?;
+                let modified = metadata.modified()?;
+                let freed = reclaimable_bytes(keep, target);
+                replace_with(&target.path, |tmp| {
+                    reflink(&keep.path, tmp)?;
+                    // 保留原文件的修改时间和权限（先设置时间，只读权限会导致无法再打开写入）
+                    fs::OpenOptions::new().write(true).open(tmp)?.set_modified(modified)?;
+                    fs::set_permissions(tmp, metadata.permissions())
+                })?;
+                Ok((freed, Some(AfterState::Reflink { source: keep.path.clone() })))
+            }
+            Action::Link { kind: LinkKind::Symbolic, relative } => {
+                let link_target = if *relative {
+                    let target_dir = target.path.parent().unwrap_or_else(|| Path::new("."));
+                    relative_path(target_dir, &keep.path)
+                } else {
+                    keep.path.clone()
+                };
+                // 目标与保留的文件是同一个 inode（硬链接）时，替换为符号链接不会释放空间
+                let freed = reclaimable_bytes(keep, target);
+                replace_with(&target.path, |tmp| symlink_file(&link_target, tmp))?;
+                Ok((freed, Some(AfterState::Symlink { target: link_target })))
+            }
+            Action::MoveTo(dir) => {
+                let to = quarantine::quarantine_path(dir, &target.path);
+                quarantine::move_file(&target.path, &to)?;
+                Ok((target.size, Some(AfterState::Move { to })))
+            }
+            Action::Trash => {
+                let trashed = trash::trash_file(&target.path)?;
+                Ok((target.size, Some(AfterState::Trash(trashed))))
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn symlink_file(original: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(original, link)
+}
+
+#[cfg(windows)]
+fn symlink_file(original: &Path, link: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(original, link)
+}
+
+/// 计算从目录 `from` 指向 `to` 的相对路径（两者都应为绝对路径）
+fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from: Vec<_> = from.components().collect();
+    let to: Vec<_> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut result = PathBuf::new();
+    for _ in common..from.len() {
+        result.push("..");
+    }
+    for component in &to[common..] {
+        result.push(component);
+    }
+    result
+}
+
+/// 使用 FICLONE ioctl 创建共享数据块的副本
+#[cfg(target_os = "linux")]
+fn reflink(src: &Path, dst: &Path) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let src_file = fs::File::open(src)?;
+    let dst_file = fs::OpenOptions::new().write(true).create_new(true).open(dst)?;
+
+    // SAFETY: 两个文件描述符在调用期间均有效
+    let ret = unsafe { libc::ioctl(dst_file.as_raw_fd(), libc::FICLONE, src_file.as_raw_fd()) };
+    if ret == 0 {
+        return Ok(());
+    }
+
+    let err = io::Error::last_os_error();
+    drop(dst_file);
+    let _ = fs::remove_file(dst);
+
+    match err.raw_os_error() {
+        Some(libc::EOPNOTSUPP) | Some(libc::ENOTTY) | Some(libc::EINVAL) => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "文件系统不支持 reflink（需要 btrfs、XFS 等）",
+        )),
+        Some(libc::EXDEV) => Err(io::Error::other("与保留的文件不在同一文件系统，无法 reflink")),
+        _ => Err(err),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn reflink(_src: &Path, _dst: &Path) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "reflink 仅支持 Linux"))
+}
+
+/// 硬链接不能跨文件系统，提前给出清晰的错误
+#[cfg(unix)]
+fn check_same_filesystem(keep: &Path, target: &Path) -> io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let target_dir = target.parent().unwrap_or_else(|| Path::new("."));
+    if fs::metadata(keep)?.dev() != fs::metadata(target_dir)?.dev() {
+        return Err(io::Error::other("与保留的文件不在同一文件系统"));
+    }
+    Ok(())
+}
+
+/// 其他平台交由 hard_link 本身报告错误
+#[cfg(not(unix))]
+fn check_same_filesystem(_keep: &Path, _target: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// 目标文件同目录下的临时文件名
+fn temp_path_for(target: &Path) -> PathBuf {
+    let name = target.file_name().unwrap_or_default().to_string_lossy();
+    target.with_file_name(format!(".{}.dupfinder-{}.tmp", name, std::process::id()))
+}
+
+/// 原子地替换目标文件：先在同一目录下用 `create` 生成临时文件，再重命名覆盖目标。
+/// 任何一步失败时目标文件保持不变
+fn replace_with<F>(target: &Path, create: F) -> io::Result<()>
+where
+    F: FnOnce(&Path) -> io::Result<()>,
+{
+    let tmp = temp_path_for(target);
+    create(&tmp)?;
+    if let Err(e) = fs::rename(&tmp, target) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// 一组重复文件的处理计划：保留 `keep`，处理 `targets` 中的文件
+#[derive(Debug, Clone)]
+pub struct GroupPlan {
+    pub keep: FileInfo,
+    pub targets: Vec<FileInfo>,
+}
+
+impl GroupPlan {
+    /// 按默认规则生成计划：保留组内第一个文件，参考目录中的文件不处理
+    pub fn from_group(group: &[FileInfo]) -> Self {
+        GroupPlan {
+            keep: group[0].clone(),
+            targets: removable_files(group).cloned().collect(),
+        }
+    }
+}
+
+/// 所有操作的统计结果
+#[derive(Debug, Default)]
+pub struct ActionSummary {
+    pub processed_files: usize,
+    /// 删除文件释放的空间
+    pub deleted_bytes: u64,
+    /// 链接共享数据回收的空间
+    pub reclaimed_bytes: u64,
+    /// 移动到隔离目录或回收站的文件大小
+    pub moved_bytes: u64,
+    pub failed_files: usize,
+    /// 操作日志或恢复清单写入失败，剩余的文件没有处理
+    pub aborted: bool,
+}
+
+/// 处理目标文件后能释放的空间：与保留文件是同一个 inode，
+/// 或者还有其他硬链接（nlink > 1，最后一个链接消失前数据块不会释放）时不会释放空间
+fn reclaimable_bytes(keep: &FileInfo, target: &FileInfo) -> u64 {
+    if is_same_file(&keep.path, &target.path).unwrap_or(false) {
+        return 0;
+    }
+    match fs::symlink_metadata(&target.path) {
+        Ok(metadata) if link_count(&metadata) > 1 => 0,
+        _ => target.size,
+    }
+}
+
+#[cfg(unix)]
+fn link_count(metadata: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.nlink()
+}
+
+/// 其他平台无法读取链接数，按只有一个链接计算
+#[cfg(not(unix))]
+fn link_count(_metadata: &fs::Metadata) -> u64 {
+    1
+}
+
+/// 检查目标文件是否仍与保留的文件完全相同
+fn reverify(keep: &FileInfo, target: &FileInfo) -> Result<(), String> {
+    let metadata = fs::metadata(&target.path).map_err(|e| format!("无法读取: {}", e))?;
+    if metadata.len() != target.size {
+        return Err("文件大小已变化".to_string());
+    }
+
+    match byte_compare(&keep.path, &target.path) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err("内容与保留的文件不再相同".to_string()),
+        Err(e) => Err(format!("比较失败: {}", e)),
+    }
+}
+
+/// 按计划执行操作，逐组输出处理结果；`journal` 记录每一次实际修改，供 undo 使用
+pub fn execute(
+    plans: &[GroupPlan],
+    action: &Action,
+    dry_run: bool,
+    mut journal: Option<&mut Journal>,
+) -> ActionSummary {
+    let mut summary = ActionSummary::default();
+
+    println!("\n{}", "=".repeat(70));
+    if dry_run {
+        println!("{}", "🧪 演练模式：只显示将要执行的操作，不会修改任何文件".yellow().bold());
+    } else {
+        println!("{}", "⚙️  开始处理重复文件".yellow().bold());
+    }
+    println!("{}", "=".repeat(70));
+
+    // 隔离模式需要记录恢复清单
+    let mut manifest = match action {
+        Action::MoveTo(dir) if !dry_run => match Manifest::create(dir) {
+            Ok(manifest) => Some(manifest),
+            Err(e) => {
+                eprintln!("{} {}: {}", "❌ 无法创建隔离目录".red(), dir.display(), e);
+                return summary;
+            }
+        },
+        _ => None,
+    };
+
+    for (i, plan) in plans.iter().enumerate() {
+        if plan.targets.is_empty() {
+            continue;
+        }
+
+        println!(
+            "\n{} {} {}",
+            format!("组 {}:", i + 1).bright_blue().bold(),
+            "保留".green(),
+            plan.keep.path.display()
+        );
+
+        let mut group_files = 0;
+        let mut group_bytes = 0;
+
+        for target in &plan.targets {
+            // 执行前再次确认内容相同，防止扫描后文件被修改
+            if let Err(reason) = reverify(&plan.keep, target) {
+                println!("  {} 跳过 {}: {}", "✗".red(), target.path.display(), reason);
+                summary.failed_files += 1;
+                continue;
+            }
+
+            // 记录操作日志时，无法读取原始状态的文件不处理，保证每次修改都可以撤销
+            let before = match (journal.is_some(), FileState::capture(&target.path)) {
+                (true, Err(e)) => {
+                    println!("  {} 跳过 {}: 无法读取文件状态: {}", "✗".red(), target.path.display(), e);
+                    summary.failed_files += 1;
+                    continue;
+                }
+                (_, before) => before,
+            };
+            let result = if dry_run {
+                action.preview(&plan.keep, target).map(|freed| (freed, None))
+            } else {
+                action.apply(&plan.keep, target)
+            };
+
+            match result {
+                Ok((freed, after)) => {
+                    if let (Some(journal), Some(after), Ok(before)) = (journal.as_deref_mut(), after, before) {
+                        let entry = JournalEntry {
+                            timestamp: Local::now().to_rfc3339(),
+                            path: target.path.clone(),
+                            kept_path: plan.keep.path.clone(),
+                            hash: target.full_hash.clone(),
+                            hash_algorithm: Some(journal.hash_algorithm()),
+                            before,
+                            after,
+                        };
+                        if let Err(e) = journal.append(&entry) {
+                            eprintln!("{} {}: {}", "❌ 写入操作日志失败，停止处理:".red(), target.path.display(), e);
+                            summary.aborted = true;
+                        }
+                    }
+                    if let (Some(manifest), Action::MoveTo(dir)) = (manifest.as_mut(), action) {
+                        let entry = ManifestEntry {
+                            original_path: target.path.clone(),
+                            quarantine_path: quarantine::quarantine_path(dir, &target.path),
+                            size: target.size,
+                            hash: target.full_hash.clone(),
+                            kept_path: plan.keep.path.clone(),
+                            timestamp: Local::now().to_rfc3339(),
+                        };
+                        if let Err(e) = manifest.append(&entry) {
+                            eprintln!("{} {}: {}", "❌ 写入恢复清单失败，停止处理:".red(), target.path.display(), e);
+                            summary.aborted = true;
+                        }
+                    }
+                    println!(
+                        "  {} {} {} ({})",
+                        "✓".green(),
+                        action.verb(dry_run),
+                        target.path.display(),
+                        format_size(freed)
+                    );
+                    group_files += 1;
+                    group_bytes += freed;
+                    if summary.aborted {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    println!("  {} 失败 {}: {}", "✗".red(), target.path.display(), e);
+                    summary.failed_files += 1;
+                }
+            }
+        }
+
+        println!(
+            "  {}",
+            format!(
+                "小计: {} {} 个文件，{} {}",
+                action.verb(dry_run),
+                group_files,
+                action.space_effect().label(),
+                format_size(group_bytes)
+            )
+            .dimmed()
+        );
+
+        summary.processed_files += group_files;
+        match action.space_effect() {
+            SpaceEffect::Freed => summary.deleted_bytes += group_bytes,
+            SpaceEffect::Reclaimed => summary.reclaimed_bytes += group_bytes,
+            SpaceEffect::Moved => summary.moved_bytes += group_bytes,
+        }
+        if summary.aborted {
+            break;
+        }
+    }
+
+    if let Some(manifest) = manifest {
+        println!(
+            "\n{} {}",
+            "📦 恢复清单已保存到:".green(),
+            manifest.path().display()
+        );
+        println!(
+            "{}",
+            format!("   恢复方式：dupfinder restore {}", manifest.path().display()).cyan()
+        );
+    }
+
+    if let Some(journal) = journal {
+        println!("\n{} {}", "📜 操作日志已保存到:".green(), journal.path().display());
+        println!(
+            "{}",
+            format!("   撤销方式：dupfinder undo {}", journal.path().display()).cyan()
+        );
+    }
+
+    summary
+}
+
+/// 输出操作统计
+pub fn display_summary(summary: &ActionSummary, action: &Action, dry_run: bool) {
+    println!("\n{}", "=".repeat(70));
+    println!("{}", "📈 处理结果:".cyan().bold());
+    println!("  {}文件数: {}", action.verb(dry_run), summary.processed_files);
+    println!(
+        "  删除释放空间: {} ({} bytes)",
+        format_size(summary.deleted_bytes),
+        summary.deleted_bytes
+    );
+    println!(
+        "  链接回收空间: {} ({} bytes)",
+        format_size(summary.reclaimed_bytes),
+        summary.reclaimed_bytes
+    );
+    if summary.moved_bytes > 0 {
+        println!(
+            "  移出空间: {} ({} bytes，清空隔离目录或回收站后释放)",
+            format_size(summary.moved_bytes),
+            summary.moved_bytes
+        );
+    }
+    if summary.failed_files > 0 {
+        println!("  {}", format!("跳过/失败: {} 个文件", summary.failed_files).red());
+    }
+    if summary.aborted {
+        println!("  {}", "操作日志或恢复清单写入失败，剩余的文件未处理".red());
+    }
+    println!("{}", "=".repeat(70));
+}
+
+/// 收集每组的处理计划
+pub fn plan_groups(groups: &[Vec<FileInfo>]) -> Vec<GroupPlan> {
+    groups.iter().map(|group| GroupPlan::from_group(group)).collect()
+}
@@ -0,0 +1,212 @@
+// ============================================================================
+// 哈希缓存
+// 以 (设备号, inode, 文件大小, 修改时间, 哈希算法) 为键保存已计算的哈希，
+// 重复扫描同一目录时只需要为新增或修改过的文件重新计算
+// ============================================================================
+
+use crate::hasher::HashAlgorithm;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, Metadata};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// 缓存中的哈希类型
+#[derive(Debug, Clone, Copy)]
+pub enum HashKind {
+    /// 前 8KB 的部分哈希
+    Partial,
+    /// 完整文件哈希
+    Full,
+}
+
+/// 唯一标识某个版本的文件内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct FileKey {
+    dev: u64,
+    ino: u64,
+    size: u64,
+    mtime_ns: i64,
+}
+
+impl FileKey {
+    #[cfg(unix)]
+    fn from_metadata(metadata: &Metadata) -> Option<Self> {
+        use std::os::unix::fs::MetadataExt;
+        Some(FileKey {
+            dev: metadata.dev(),
+            ino: metadata.ino(),
+            size: metadata.len(),
+            mtime_ns: metadata
+                .mtime()
+                .saturating_mul(1_000_000_000)
+                .saturating_add(metadata.mtime_nsec()),
+        })
+    }
+
+    /// 非 Unix 平台无法稳定获取 inode，不使用缓存
+    #[cfg(not(unix))]
+    fn from_metadata(_metadata: &Metadata) -> Option<Self> {
+        None
+    }
+}
+
+/// 缓存文件中的一行记录（JSON Lines 格式）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheRecord {
+    key: FileKey,
+    /// 旧版本缓存没有该字段，均为 MD5
+    #[serde(default)]
+    algorithm: HashAlgorithm,
+    path: PathBuf,
+    partial_hash: Option<String>,
+    full_hash: Option<String>,
+}
+
+pub struct HashCache {
+    path: PathBuf,
+    algorithm: HashAlgorithm,
+    entries: Mutex<HashMap<(FileKey, HashAlgorithm), CacheRecord>>,
+    dirty: AtomicBool,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl HashCache {
+    /// 默认缓存位置：$XDG_CACHE_HOME/dupfinder/hashes.db 或 ~/.cache/dupfinder/hashes.db
+    pub fn default_path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+            .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))?;
+        Some(base.join("dupfinder").join("hashes.db"))
+    }
+
+    /// 读取缓存文件，文件不存在时返回空缓存；无法解析的行直接忽略。
+    /// 其他算法的记录会原样保留，但本次扫描只使用 `algorithm` 的记录
+    pub fn load(path: &Path, algorithm: HashAlgorithm) -> io::Result<Self> {
+        let mut entries = HashMap::new();
+
+        match File::open(path) {
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    let line = line?;
+                    if let Ok(record) = serde_json::from_str::<CacheRecord>(&line) {
+                        entries.insert((record.key, record.algorithm), record);
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        Ok(HashCache {
+            path: path.to_path_buf(),
+            algorithm,
+            entries: Mutex::new(entries),
+            dirty: AtomicBool::new(false),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 命中次数和未命中次数
+    pub fn stats(&self) -> (usize, usize) {
+        (self.hits.load(Ordering::Relaxed), self.misses.load(Ordering::Relaxed))
+    }
+
+    /// 优先返回缓存中的哈希，否则调用 `compute` 计算并写入缓存
+    pub fn get_or_compute<F>(&self, path: &Path, kind: HashKind, compute: F) -> io::Result<String>
+    where
+        F: FnOnce() -> io::Result<String>,
+    {
+        // 在计算之前记录文件状态：如果文件在计算过程中被修改，
+        // 下次扫描时修改时间不同，自然不会命中这条记录
+        let key = match fs::metadata(path).ok().and_then(|m| FileKey::from_metadata(&m)) {
+            Some(key) => key,
+            None => return compute(),
+        };
+
+        let cached = self.entries.lock().unwrap().get(&(key, self.algorithm)).and_then(|record| match kind {
+            HashKind::Partial => record.partial_hash.clone(),
+            HashKind::Full => record.full_hash.clone(),
+        });
+        if let Some(hash) = cached {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(hash);
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let hash = compute()?;
+
+        let mut entries = self.entries.lock().unwrap();
+        let record = entries.entry((key, self.algorithm)).or_insert_with(|| CacheRecord {
+            key,
+            algorithm: self.algorithm,
+            path: path.to_path_buf(),
+            partial_hash: None,
+            full_hash: None,
+        });
+        record.path = path.to_path_buf();
+        match kind {
+            HashKind::Partial => record.partial_hash = Some(hash.clone()),
+            HashKind::Full => record.full_hash = Some(hash.clone()),
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+
+        Ok(hash)
+    }
+
+    /// 删除过期的记录（文件已不存在，或大小/修改时间/inode 已变化），返回删除的数量
+    pub fn prune(&self) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+
+        entries.retain(|(key, _), record| {
+            fs::metadata(&record.path)
+                .ok()
+                .and_then(|m| FileKey::from_metadata(&m))
+                .is_some_and(|current| current == *key)
+        });
+
+        let removed = before - entries.len();
+        if removed > 0 {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+        removed
+    }
+
+    /// 将缓存写回磁盘（先写临时文件再重命名，避免中途中断损坏缓存）
+    pub fn save(&self) -> io::Result<()> {
+        if !self.dirty.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut tmp_name = self.path.file_name().unwrap_or_default().to_os_string();
+        tmp_name.push(".tmp");
+        let tmp_path = self.path.with_file_name(tmp_name);
+        {
+            let mut writer = BufWriter::new(File::create(&tmp_path)?);
+            for record in self.entries.lock().unwrap().values() {
+                serde_json::to_writer(&mut writer, record)?;
+                writer.write_all(b"\n")?;
+            }
+            writer.flush()?;
+        }
+        fs::rename(&tmp_path, &self.path)?;
+
+        self.dirty.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+}
@@ -0,0 +1,633 @@
+// ============================================================================
+// 遍历过滤：在遍历目录时就跳过不需要的文件和目录
+// 被排除的目录不会被进入（WalkDir::filter_entry），.git、node_modules 等不会被遍历
+// ============================================================================
+
+use crate::{format_size, quarantine, ScanArgs};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use clap::ValueEnum;
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use regex::{Regex, RegexSet};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// --respect-ignore 时额外读取的忽略规则文件（与 .gitignore 语法相同）
+pub const IGNORE_FILE_NAME: &str = ".dupfinderignore";
+
+/// 同时指定 -p 和 --regex 时的组合方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FilterLogic {
+    /// 任一条件匹配即可
+    Or,
+    /// 所有条件都必须匹配
+    And,
+}
+
+/// 遍历时应用的过滤条件
+#[derive(Clone)]
+pub struct WalkFilter {
+    /// -p：glob 模式
+    include_glob: Option<GlobSet>,
+    /// --regex：正则表达式
+    include_regex: Option<Regex>,
+    /// --match-path：-p 和 --regex 匹配相对路径而不是文件名
+    match_path: bool,
+    /// --filter-logic：-p 和 --regex 的组合方式
+    logic: FilterLogic,
+    /// --exclude：匹配相对路径的 glob 模式
+    exclude_glob: Option<GlobSet>,
+    /// --exclude 中以 / 结尾的模式：只匹配目录（与 .gitignore 相同）
+    exclude_dir_glob: Option<GlobSet>,
+    /// --exclude-regex：匹配相对路径的正则表达式
+    exclude_regex: Option<RegexSet>,
+    /// --min-size / --max-size：文件大小范围（包含边界）
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    /// --include-empty：是否包含空文件
+    include_empty: bool,
+    /// --older-than：修改时间早于该时间点
+    modified_before: Option<SystemTime>,
+    /// --newer-than：修改时间不早于该时间点
+    modified_after: Option<SystemTime>,
+    /// --move-to 的隔离目录（已存在时）：位于扫描目录中时不遍历，避免把之前隔离的文件当作重复文件
+    quarantine_dir: Option<PathBuf>,
+}
+
+impl WalkFilter {
+    /// 根据命令行参数构建过滤条件。
+    /// --match-path 时 -p 中的 * 不匹配 /，需要用 ** 匹配任意层目录；
+    /// 排除模式中的 * 同样不匹配 /，不含 / 的排除模式在任意深度匹配（与 .gitignore 相同），
+    /// 例如 node_modules 同时匹配 node_modules 和 web/node_modules
+    pub fn new(args: &ScanArgs) -> Result<Self, String> {
+        if let (Some(min), Some(max)) = (args.min_size, args.max_size) {
+            if min > max {
+                return Err(format!(
+                    "--min-size（{}）不能大于 --max-size（{}）",
+                    format_size(min),
+                    format_size(max)
+                ));
+            }
+        }
+
+        if let (Some(before), Some(after)) = (args.older_than, args.newer_than) {
+            if before <= after {
+                return Err(format!(
+                    "--older-than 的时间点（{}）必须晚于 --newer-than 的时间点（{}），否则没有文件符合条件",
+                    format_time(before),
+                    format_time(after)
+                ));
+            }
+        }
+
+        let include_glob = if args.patterns.is_empty() {
+            None
+        } else {
+            let mut builder = GlobSetBuilder::new();
+            for pattern in &args.patterns {
+                let glob = GlobBuilder::new(pattern)
+                    .literal_separator(args.match_path)
+                    .build()
+                    .map_err(|e| format!("无效的 glob 模式 {}: {}", pattern, e))?;
+                builder.add(glob);
+            }
+            Some(builder.build().map_err(|e| format!("构建 glob 集合失败: {}", e))?)
+        };
+
+        let include_regex = match args.regex_pattern {
+            Some(ref pattern) => {
+                Some(Regex::new(pattern).map_err(|e| format!("无效的正则表达式 {}: {}", pattern, e))?)
+            }
+            None => None,
+        };
+
+        let (dir_excludes, excludes): (Vec<&String>, Vec<&String>) =
+            args.excludes.iter().partition(|pattern| pattern.ends_with('/'));
+        let exclude_glob = exclude_glob_set(&excludes)?;
+        let exclude_dir_glob = exclude_glob_set(&dir_excludes)?;
+
+        let exclude_regex = if args.exclude_regexes.is_empty() {
+            None
+        } else {
+            Some(RegexSet::new(&args.exclude_regexes).map_err(|e| format!("无效的排除正则表达式: {}", e))?)
+        };
+
+        Ok(WalkFilter {
+            include_glob,
+            include_regex,
+            match_path: args.match_path,
+            logic: args.filter_logic,
+            exclude_glob,
+            exclude_dir_glob,
+            exclude_regex,
+            min_size: args.min_size,
+            max_size: args.max_size,
+            include_empty: args.include_empty,
+            modified_before: args.older_than,
+            modified_after: args.newer_than,
+            quarantine_dir: args.move_to.as_ref().and_then(|dir| dir.canonicalize().ok()),
+        })
+    }
+
+    /// 修改时间是否在范围内；指定了时间条件时，无法读取修改时间的文件不包含
+    pub fn time_matches(&self, modified: Option<SystemTime>) -> bool {
+        if self.modified_before.is_none() && self.modified_after.is_none() {
+            return true;
+        }
+        let Some(modified) = modified else {
+            return false;
+        };
+        self.modified_before.is_none_or(|before| modified < before)
+            && self.modified_after.is_none_or(|after| modified >= after)
+    }
+
+    /// 文件大小是否在范围内（只使用目录项的元数据，不需要打开文件）。
+    /// 空文件默认跳过，--include-empty 时只要 --min-size 允许就包含
+    pub fn size_matches(&self, size: u64) -> bool {
+        if size == 0 && !self.include_empty {
+            return false;
+        }
+        self.min_size.is_none_or(|min| size >= min) && self.max_size.is_none_or(|max| size <= max)
+    }
+
+    /// 是否设置了文件名过滤条件
+    pub fn has_includes(&self) -> bool {
+        self.include_glob.is_some() || self.include_regex.is_some()
+    }
+
+    /// 检查文件是否应该被包含在扫描中。默认匹配文件名，--match-path 时匹配相对于扫描目录的路径；
+    /// 同时指定 -p 和 --regex 时按 --filter-logic 组合（默认任一匹配即可）
+    pub fn includes(&self, path: &Path, relative: &Path) -> bool {
+        // 如果没有指定任何过滤条件，包含所有文件
+        if !self.has_includes() {
+            return true;
+        }
+
+        let subject = if self.match_path {
+            Cow::Owned(slash_path(relative))
+        } else {
+            match path.file_name().and_then(|n| n.to_str()) {
+                Some(name) => Cow::Borrowed(name),
+                None => return false,
+            }
+        };
+
+        let glob = self.include_glob.as_ref().map(|globset| globset.is_match(subject.as_ref()));
+        let regex = self.include_regex.as_ref().map(|regex| regex.is_match(&subject));
+        match self.logic {
+            FilterLogic::Or => glob.unwrap_or(false) || regex.unwrap_or(false),
+            FilterLogic::And => glob.unwrap_or(true) && regex.unwrap_or(true),
+        }
+    }
+
+    /// 是否设置了排除条件
+    pub fn has_excludes(&self) -> bool {
+        self.exclude_glob.is_some() || self.exclude_dir_glob.is_some() || self.exclude_regex.is_some()
+    }
+
+    /// 遍历时是否跳过该目录项：被排除的路径，以及隔离目录（本次的 --move-to 或含有标记文件的目录）
+    pub fn prunes(&self, path: &Path, relative: &Path, is_dir: bool) -> bool {
+        if is_dir && (self.quarantine_dir.as_deref() == Some(path) || quarantine::is_quarantine_dir(path)) {
+            return true;
+        }
+        self.excludes(relative, is_dir)
+    }
+
+    /// 相对于扫描根目录的路径是否被排除（目录被排除时，其中的内容都不会被遍历）；
+    /// 以 / 结尾的排除模式只匹配目录
+    pub fn excludes(&self, relative: &Path, is_dir: bool) -> bool {
+        if !self.has_excludes() {
+            return false;
+        }
+        let relative = slash_path(relative);
+
+        if let Some(ref globset) = self.exclude_glob {
+            if globset.is_match(&relative) {
+                return true;
+            }
+        }
+        if let Some(ref globset) = self.exclude_dir_glob {
+            if is_dir && globset.is_match(&relative) {
+                return true;
+            }
+        }
+        if let Some(ref regex) = self.exclude_regex {
+            if regex.is_match(&relative) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// 编译一组排除模式，不含 / 的模式（忽略末尾的 /）同时在任意深度匹配
+fn exclude_glob_set(patterns: &[&String]) -> Result<Option<GlobSet>, String> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let pattern = pattern.trim_end_matches('/');
+        builder.add(exclude_glob(pattern)?);
+        if !pattern.contains('/') {
+            builder.add(exclude_glob(&format!("**/{}", pattern))?);
+        }
+    }
+    Ok(Some(builder.build().map_err(|e| format!("构建排除模式失败: {}", e))?))
+}
+
+/// 排除模式：* 和 ? 不匹配 /，build/* 只排除 build 下的直接子项
+fn exclude_glob(pattern: &str) -> Result<Glob, String> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map_err(|e| format!("无效的排除模式 {}: {}", pattern, e))
+}
+
+/// 统一使用 / 作为分隔符的相对路径，同一个模式在各个平台上含义相同
+fn slash_path(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 解析文件大小，如 10K、1.5MiB、2G、4096。
+/// 单位不区分大小写，K/KB/KiB 都按 1024 计算（与输出中的 KB、MB、GB 一致）
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+
+    let value: f64 = number
+        .parse()
+        .map_err(|_| format!("无效的大小: {}（示例: 10K、1.5MiB、2G）", s))?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        _ => return Err(format!("无法识别的大小单位: {}（可用 B、K、M、G、T）", unit.trim())),
+    };
+
+    let bytes = value * multiplier as f64;
+    if bytes > u64::MAX as f64 {
+        return Err(format!("大小超出范围: {}", s));
+    }
+    Ok(bytes.round() as u64)
+}
+
+/// 解析时间条件：相对于现在的时长（30d、12h、1w2d，单位 s/m/h/d/w），
+/// 或者绝对时间（2024-01-31、2024-01-31 08:00、2024-01-31T08:00:00、RFC 3339），按本地时区解释
+pub fn parse_time(s: &str) -> Result<SystemTime, String> {
+    parse_time_in(&Local, s)
+}
+
+/// 按时区 `tz` 解释不带时区的时间；夏令时重复的时间取较早的一个，跳过的时间报错
+fn parse_time_in<Tz: TimeZone>(tz: &Tz, s: &str) -> Result<SystemTime, String> {
+    let s = s.trim();
+    if let Some(duration) = parse_duration(s) {
+        return SystemTime::now()
+            .checked_sub(duration)
+            .ok_or_else(|| format!("时长超出范围: {}", s));
+    }
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Ok(time.into());
+    }
+    let naive = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        });
+    match naive {
+        Some(naive) => tz
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(SystemTime::from)
+            .ok_or_else(|| format!("本地时区中不存在该时间: {}", s)),
+        None => Err(format!(
+            "无效的时间: {}（示例: 30d、12h、1w2d、2024-01-31、2024-01-31 08:00）",
+            s
+        )),
+    }
+}
+
+/// 解析 30d、12h、1w2d 这样的时长（空字符串不是时长）
+fn parse_duration(s: &str) -> Option<Duration> {
+    if s.is_empty() {
+        return None;
+    }
+    let mut total: u64 = 0;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        if digits == 0 {
+            return None;
+        }
+        let value: u64 = rest[..digits].parse().ok()?;
+        let unit = rest[digits..].chars().next()?;
+        let seconds = match unit.to_ascii_lowercase() {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            _ => return None,
+        };
+        total = total.checked_add(value.checked_mul(seconds)?)?;
+        rest = &rest[digits + unit.len_utf8()..];
+    }
+    Some(Duration::from_secs(total))
+}
+
+/// 输出中使用的本地时间
+pub fn format_time(time: SystemTime) -> String {
+    DateTime::<Local>::from(time).format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Args;
+    use chrono::{FixedOffset, LocalResult};
+    use clap::Parser;
+
+    fn filter(extra: &[&str]) -> WalkFilter {
+        let argv = ["dupfinder", "."].iter().chain(extra);
+        WalkFilter::new(&Args::parse_from(argv).scan).unwrap()
+    }
+
+    fn excluded(filter: &WalkFilter, relative: &str) -> bool {
+        filter.excludes(Path::new(relative), false)
+    }
+
+    fn excluded_dir(filter: &WalkFilter, relative: &str) -> bool {
+        filter.excludes(Path::new(relative), true)
+    }
+
+    #[test]
+    fn parse_size_units() {
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert_eq!(parse_size("0"), Ok(0));
+        assert_eq!(parse_size("10b"), Ok(10));
+        assert_eq!(parse_size("10K"), Ok(10 * 1024));
+        assert_eq!(parse_size("10kb"), Ok(10 * 1024));
+        assert_eq!(parse_size("10 KiB"), Ok(10 * 1024));
+        assert_eq!(parse_size("1.5MiB"), Ok(1_572_864));
+        assert_eq!(parse_size("2G"), Ok(2 << 30));
+        assert_eq!(parse_size("1T"), Ok(1 << 40));
+        assert_eq!(parse_size(" 1.5GiB "), Ok(1_610_612_736));
+    }
+
+    #[test]
+    fn parse_size_rounds_fractional_bytes() {
+        assert_eq!(parse_size("0.5"), Ok(1));
+        assert_eq!(parse_size("0.4"), Ok(0));
+        assert_eq!(parse_size("1.001K"), Ok(1025));
+        assert_eq!(parse_size("0.1M"), Ok(104_858));
+    }
+
+    #[test]
+    fn parse_size_rejects_invalid_input() {
+        for input in ["", "K", "-1K", "1.2.3M", "10X", "10 MB extra", "1e3", "99999999999T"] {
+            assert!(parse_size(input).is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn size_matches_bounds_and_empty_files() {
+        let f = filter(&["--min-size", "1K", "--max-size", "2K"]);
+        assert!(!f.size_matches(1023));
+        assert!(f.size_matches(1024));
+        assert!(f.size_matches(2048));
+        assert!(!f.size_matches(2049));
+
+        assert!(!filter(&[]).size_matches(0));
+        assert!(filter(&["--include-empty"]).size_matches(0));
+        assert!(!filter(&["--include-empty", "--min-size", "1"]).size_matches(0));
+        assert!(filter(&["--include-empty", "--max-size", "0"]).size_matches(0));
+
+        let args = Args::parse_from(["dupfinder", ".", "--min-size", "2K", "--max-size", "1K"]).scan;
+        assert!(WalkFilter::new(&args).is_err());
+    }
+
+    #[test]
+    fn parse_duration_units() {
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        // m 是分钟而不是月
+        assert_eq!(parse_duration("90m"), Some(Duration::from_secs(90 * 60)));
+        assert_eq!(parse_duration("12H"), Some(Duration::from_secs(12 * 3600)));
+        assert_eq!(parse_duration("1w2d"), Some(Duration::from_secs(9 * 86400)));
+        assert_eq!(parse_duration("1d1d"), Some(Duration::from_secs(2 * 86400)));
+    }
+
+    #[test]
+    fn parse_duration_rejects_invalid_input() {
+        for input in ["", "30", "d", "5x", "1.5d", "-1d", "1d 2h", "99999999999999999999d"] {
+            assert_eq!(parse_duration(input), None, "{:?}", input);
+        }
+        assert!(parse_time("").is_err());
+        assert!(parse_time("  ").is_err());
+    }
+
+    #[test]
+    fn parse_time_relative_to_now() {
+        let before = SystemTime::now();
+        let time = parse_time("1h").unwrap();
+        let after = SystemTime::now();
+        assert!(time >= before - Duration::from_secs(3600));
+        assert!(time <= after - Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn parse_time_rfc3339_ignores_time_zone() {
+        let time = parse_time_in(&Eastern2024, "2024-01-31T08:00:00Z").unwrap();
+        assert_eq!(time, SystemTime::UNIX_EPOCH + Duration::from_secs(1_706_688_000));
+    }
+
+    #[test]
+    fn parse_time_in_fixed_offset() {
+        let tz = FixedOffset::east_opt(8 * 3600).unwrap();
+        let time = parse_time_in(&tz, "2024-01-31 08:00").unwrap();
+        assert_eq!(time, SystemTime::UNIX_EPOCH + Duration::from_secs(1_706_659_200));
+    }
+
+    /// 2024 年的美国东部时间：3 月 10 日 07:00 UTC 到 11 月 3 日 06:00 UTC 为夏令时（UTC-4），其余为 UTC-5
+    #[derive(Clone)]
+    struct Eastern2024;
+
+    impl Eastern2024 {
+        fn standard() -> FixedOffset {
+            FixedOffset::west_opt(5 * 3600).unwrap()
+        }
+
+        fn daylight() -> FixedOffset {
+            FixedOffset::west_opt(4 * 3600).unwrap()
+        }
+    }
+
+    impl TimeZone for Eastern2024 {
+        type Offset = FixedOffset;
+
+        fn from_offset(_offset: &FixedOffset) -> Self {
+            Eastern2024
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            // 夏令时在前：重复的时间中夏令时的那个更早
+            let valid: Vec<FixedOffset> = [Self::daylight(), Self::standard()]
+                .into_iter()
+                .filter(|offset| self.offset_from_utc_datetime(&(*local - *offset)) == *offset)
+                .collect();
+            match valid[..] {
+                [] => LocalResult::None,
+                [offset] => LocalResult::Single(offset),
+                [earlier, later] => LocalResult::Ambiguous(earlier, later),
+                _ => unreachable!(),
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            let start = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap().and_hms_opt(7, 0, 0).unwrap();
+            let end = NaiveDate::from_ymd_opt(2024, 11, 3).unwrap().and_hms_opt(6, 0, 0).unwrap();
+            if (start..end).contains(utc) {
+                Self::daylight()
+            } else {
+                Self::standard()
+            }
+        }
+    }
+
+    #[test]
+    fn parse_time_handles_dst_transitions() {
+        let epoch = |s: &str| {
+            parse_time_in(&Eastern2024, s).map(|t| t.duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs())
+        };
+
+        // 夏令时开始：02:00-03:00 不存在
+        assert!(epoch("2024-03-10 02:30").unwrap_err().contains("不存在"));
+        assert_eq!(epoch("2024-03-10 03:30"), Ok(1_710_055_800));
+        // 夏令时结束：01:00-02:00 出现两次，取较早的（夏令时）
+        assert_eq!(epoch("2024-11-03 01:30"), Ok(1_730_611_800));
+        // 只有日期时为当天 00:00
+        assert_eq!(epoch("2024-01-31"), epoch("2024-01-31T00:00:00"));
+        assert_eq!(epoch("2024-01-31"), Ok(1_706_677_200));
+    }
+
+    fn included(filter: &WalkFilter, relative: &str) -> bool {
+        let relative = Path::new(relative);
+        filter.includes(&Path::new("/scan").join(relative), relative)
+    }
+
+    #[test]
+    fn includes_everything_without_patterns() {
+        for logic in ["or", "and"] {
+            let f = filter(&["--filter-logic", logic]);
+            assert!(included(&f, "a.txt"));
+            assert!(included(&f, "dir/b"));
+        }
+    }
+
+    #[test]
+    fn includes_absent_filter_is_false_under_or_and_true_under_and() {
+        // 只有 -p：Or 时缺失的 --regex 不算匹配，And 时不构成限制，结果都由 -p 决定
+        for logic in ["or", "and"] {
+            let f = filter(&["-p", "*.jpg", "--filter-logic", logic]);
+            assert!(included(&f, "a.jpg"), "{}", logic);
+            assert!(!included(&f, "a.png"), "{}", logic);
+
+            let f = filter(&["--regex", r"^IMG_\d+", "--filter-logic", logic]);
+            assert!(included(&f, "IMG_001.png"), "{}", logic);
+            assert!(!included(&f, "a.png"), "{}", logic);
+        }
+    }
+
+    #[test]
+    fn includes_combines_glob_and_regex_by_logic() {
+        let or = filter(&["-p", "*.jpg", "--regex", r"^IMG_"]);
+        let and = filter(&["-p", "*.jpg", "--regex", r"^IMG_", "--filter-logic", "and"]);
+        for (name, any, all) in [
+            ("IMG_1.jpg", true, true),
+            ("a.jpg", true, false),
+            ("IMG_1.png", true, false),
+            ("a.png", false, false),
+        ] {
+            assert_eq!(included(&or, name), any, "{}", name);
+            assert_eq!(included(&and, name), all, "{}", name);
+        }
+    }
+
+    #[test]
+    fn includes_matches_file_name_unless_match_path() {
+        let f = filter(&["-p", "photos/*.jpg"]);
+        assert!(!included(&f, "photos/a.jpg"));
+
+        let f = filter(&["--match-path", "-p", "photos/*.jpg", "--regex", "^docs/"]);
+        assert!(included(&f, "photos/a.jpg"));
+        // --match-path 时 * 不匹配 /
+        assert!(!included(&f, "photos/2024/a.jpg"));
+        assert!(!included(&f, "backup/photos/a.jpg"));
+        assert!(included(&f, "docs/deep/a.txt"));
+
+        let f = filter(&["--match-path", "-p", "**/photos/**"]);
+        assert!(included(&f, "backup/photos/2024/a.jpg"));
+    }
+
+    #[test]
+    fn exclude_without_slash_matches_at_any_depth() {
+        let f = filter(&["--exclude", "node_modules", "--exclude", "*.tmp"]);
+        assert!(excluded(&f, "node_modules"));
+        assert!(excluded(&f, "web/app/node_modules"));
+        assert!(excluded(&f, "a.tmp"));
+        assert!(excluded(&f, "deep/dir/a.tmp"));
+        assert!(!excluded(&f, "node_modules_backup"));
+        assert!(!excluded(&f, "a.tmp.txt"));
+    }
+
+    #[test]
+    fn exclude_star_does_not_cross_directories() {
+        let f = filter(&["--exclude", "build/*", "--exclude", "docs/*.md"]);
+        assert!(excluded(&f, "build/cache"));
+        assert!(!excluded(&f, "sub/build/cache"));
+        assert!(excluded(&f, "docs/a.md"));
+        assert!(!excluded(&f, "docs/api/a.md"));
+
+        let f = filter(&["--exclude", "build/**"]);
+        assert!(excluded(&f, "build/cache/deep"));
+    }
+
+    #[test]
+    fn exclude_with_slash_is_anchored_to_root() {
+        let f = filter(&["--exclude", "build/cache/"]);
+        assert!(excluded_dir(&f, "build/cache"));
+        assert!(!excluded_dir(&f, "web/build/cache"));
+    }
+
+    #[test]
+    fn exclude_with_trailing_slash_matches_only_directories() {
+        let f = filter(&["--exclude", "build/", "--exclude", "*.tmp"]);
+        assert!(excluded_dir(&f, "build"));
+        assert!(excluded_dir(&f, "src/build"));
+        assert!(!excluded(&f, "build"));
+        assert!(!excluded(&f, "src/build"));
+        // 不以 / 结尾的模式同时匹配文件和目录
+        assert!(excluded(&f, "a.tmp"));
+        assert!(excluded_dir(&f, "cache.tmp"));
+    }
+}
@@ -0,0 +1,111 @@
+// ============================================================================
+// 哈希算法抽象
+// 第 2、3 层验证通过 FileHasher trait 计算哈希，具体算法由 --hash 选择
+// ============================================================================
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// 可选的哈希算法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    /// MD5（默认，兼容旧版本报告）
+    #[default]
+    Md5,
+    /// SHA-256（适合审计报告）
+    Sha256,
+    /// BLAKE3（快速的加密哈希）
+    Blake3,
+    /// xxHash3 128 位（最快的非加密哈希，适合批量扫描）
+    Xxh3,
+}
+
+impl HashAlgorithm {
+    /// 算法名称（用于输出和 JSON 报告）
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Blake3 => "blake3",
+            HashAlgorithm::Xxh3 => "xxh3",
+        }
+    }
+
+    /// 创建一个新的增量哈希计算器
+    pub fn hasher(self) -> Box<dyn FileHasher> {
+        match self {
+            HashAlgorithm::Md5 => Box::new(md5::Context::new()),
+            HashAlgorithm::Sha256 => Box::new(sha2::Sha256::new()),
+            HashAlgorithm::Blake3 => Box::new(blake3::Hasher::new()),
+            HashAlgorithm::Xxh3 => Box::new(xxhash_rust::xxh3::Xxh3::new()),
+        }
+    }
+}
+
+/// 计算整个文件的哈希
+pub fn hash_file(path: &Path, algorithm: HashAlgorithm) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = algorithm.hasher();
+    let mut buffer = vec![0u8; 8192];
+
+    loop {
+        let bytes_read = file.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+
+    Ok(hasher.finish())
+}
+
+/// 增量计算哈希，结果统一输出为小写十六进制字符串
+pub trait FileHasher: Send {
+    fn update(&mut self, data: &[u8]);
+    fn finish(self: Box<Self>) -> String;
+}
+
+impl FileHasher for md5::Context {
+    fn update(&mut self, data: &[u8]) {
+        self.consume(data);
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        format!("{:x}", self.compute())
+    }
+}
+
+impl FileHasher for sha2::Sha256 {
+    fn update(&mut self, data: &[u8]) {
+        Digest::update(self, data);
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        format!("{:x}", self.finalize())
+    }
+}
+
+impl FileHasher for blake3::Hasher {
+    fn update(&mut self, data: &[u8]) {
+        blake3::Hasher::update(self, data);
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        self.finalize().to_hex().to_string()
+    }
+}
+
+impl FileHasher for xxhash_rust::xxh3::Xxh3 {
+    fn update(&mut self, data: &[u8]) {
+        xxhash_rust::xxh3::Xxh3::update(self, data);
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        format!("{:032x}", self.digest128())
+    }
+}
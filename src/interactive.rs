@@ -0,0 +1,143 @@
+// ============================================================================
+// 交互模式：逐组选择要保留的文件（类似 fdupes -d）
+// 输入编号保留对应文件，all 保留全部，skip 跳过本组，quit 结束选择
+// ============================================================================
+
+use crate::actions::GroupPlan;
+use crate::{format_size, DupFinder, FileInfo};
+use chrono::{DateTime, Local};
+use colored::*;
+use std::io::{self, BufRead, Write};
+
+/// 一组的选择结果
+enum Choice {
+    /// 保留这些下标对应的文件
+    Keep(Vec<usize>),
+    /// 本组不处理
+    Skip,
+    /// 结束选择，之后的组都不处理
+    Quit,
+}
+
+/// 解析用户输入；空输入表示使用默认保留的文件（组内第一个）
+fn parse_choice(input: &str, len: usize) -> Result<Choice, String> {
+    let input = input.trim();
+    match input.to_lowercase().as_str() {
+        "" => return Ok(Choice::Keep(vec![0])),
+        "all" | "a" => return Ok(Choice::Keep((0..len).collect())),
+        "skip" | "s" => return Ok(Choice::Skip),
+        "quit" | "q" => return Ok(Choice::Quit),
+        _ => {}
+    }
+
+    let mut selected = Vec::new();
+    for token in input.split(|c: char| c == ',' || c.is_whitespace()).filter(|t| !t.is_empty()) {
+        let n: usize = token
+            .parse()
+            .map_err(|_| format!("无法识别的输入: {}", token))?;
+        if n == 0 || n > len {
+            return Err(format!("编号超出范围: {}（1-{}）", n, len));
+        }
+        if !selected.contains(&(n - 1)) {
+            selected.push(n - 1);
+        }
+    }
+    Ok(Choice::Keep(selected))
+}
+
+fn format_mtime(file: &FileInfo) -> String {
+    file.modified
+        .map(|t| DateTime::<Local>::from(t).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// 显示一组文件
+fn show_group(finder: &DupFinder, index: usize, total: usize, group: &[FileInfo]) {
+    println!(
+        "\n{} {}",
+        format!("组 {}/{}:", index + 1, total).bright_blue().bold(),
+        format!("每个文件 {}", format_size(group[0].size)).dimmed()
+    );
+    for (j, file) in group.iter().enumerate() {
+        let mut path_display = finder.format_path(file);
+        if let Some(label) = finder.root_label(file) {
+            path_display = format!("{} {}", format!("[{}]", label).dimmed(), path_display);
+        }
+        if file.is_reference {
+            path_display = format!("{} {}", path_display, "(参考)".dimmed());
+        }
+        println!(
+            "  {} {}  {}",
+            format!("[{}]", j + 1).cyan(),
+            format_mtime(file).dimmed(),
+            path_display
+        );
+    }
+}
+
+/// 逐组询问要保留的文件，生成处理计划；参考目录中的文件无论是否选中都会保留
+pub fn select_plans(finder: &DupFinder, groups: &[Vec<FileInfo>]) -> Vec<GroupPlan> {
+    println!("\n{}", "=".repeat(70));
+    println!("{}", "🖐  交互模式：选择每组要保留的文件".yellow().bold());
+    println!(
+        "{}",
+        "   输入编号保留对应文件（如 1 或 1,3），all 保留全部，skip 跳过本组，quit 结束；直接回车保留 [1]"
+            .dimmed()
+    );
+    println!("{}", "=".repeat(70));
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut plans = Vec::new();
+
+    'groups: for (i, group) in groups.iter().enumerate() {
+        show_group(finder, i, groups.len(), group);
+
+        let keep = loop {
+            print!("  保留 [1-{}, all, skip, quit]: ", group.len());
+            let _ = io::stdout().flush();
+
+            // 输入结束（EOF）时按 quit 处理
+            let input = match lines.next() {
+                Some(Ok(line)) => line,
+                _ => {
+                    println!();
+                    break 'groups;
+                }
+            };
+
+            match parse_choice(&input, group.len()) {
+                Ok(Choice::Keep(keep)) if keep.is_empty() => {
+                    println!("  {}", "至少需要保留一个文件".red());
+                }
+                Ok(Choice::Keep(keep)) => break keep,
+                Ok(Choice::Skip) => {
+                    // 保留空计划，使组编号与扫描结果一致
+                    plans.push(GroupPlan {
+                        keep: group[0].clone(),
+                        targets: Vec::new(),
+                    });
+                    continue 'groups;
+                }
+                Ok(Choice::Quit) => break 'groups,
+                Err(message) => println!("  {}", message.red()),
+            }
+        };
+
+        let targets: Vec<FileInfo> = group
+            .iter()
+            .enumerate()
+            .filter(|(j, file)| !keep.contains(j) && !file.is_reference)
+            .map(|(_, file)| file.clone())
+            .collect();
+
+        plans.push(GroupPlan {
+            keep: group[keep[0]].clone(),
+            targets,
+        });
+    }
+
+    let selected: usize = plans.iter().map(|plan| plan.targets.len()).sum();
+    println!("\n{}", format!("已选择 {} 个文件待处理", selected).cyan());
+    plans
+}
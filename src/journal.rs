@@ -0,0 +1,344 @@
+// ============================================================================
+// 操作日志：记录每一次删除 / 链接 / 移动操作（追加写入的 JSON Lines）
+// `dupfinder undo 日志文件` 可以撤销链接和移动操作，删除操作无法撤销
+// ============================================================================
+
+use crate::hasher::{self, HashAlgorithm};
+use crate::quarantine::{move_file, open_for_times};
+use crate::trash::TrashedFile;
+use chrono::Local;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 操作之前的文件状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileState {
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    /// Unix 权限位
+    pub mode: Option<u32>,
+}
+
+impl FileState {
+    pub fn capture(path: &Path) -> io::Result<Self> {
+        let metadata = fs::symlink_metadata(path)?;
+        Ok(FileState {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            mode: file_mode(&metadata),
+        })
+    }
+
+    /// 把修改时间和权限恢复到记录的状态。
+    /// 先设置修改时间：恢复只读权限之后就无法再打开文件修改时间
+    fn restore_attributes(&self, path: &Path) -> io::Result<()> {
+        if let Some(modified) = self.modified {
+            open_for_times(path)?.set_modified(modified)?;
+        }
+        #[cfg(unix)]
+        if let Some(mode) = self.mode {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+/// 操作之后的文件状态（同时表示执行了哪种操作）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "operation", rename_all = "snake_case")]
+pub enum AfterState {
+    /// 文件已删除
+    Delete,
+    /// 替换为指向 `target` 的硬链接
+    HardLink { target: PathBuf },
+    /// 替换为 `source` 的 reflink 克隆
+    Reflink { source: PathBuf },
+    /// 替换为内容为 `target` 的符号链接
+    Symlink { target: PathBuf },
+    /// 移动到 `to`
+    Move { to: PathBuf },
+    /// 移到回收站
+    Trash(TrashedFile),
+}
+
+/// 日志中的一条记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub timestamp: String,
+    /// 被处理的文件
+    pub path: PathBuf,
+    /// 组内保留的文件
+    pub kept_path: PathBuf,
+    pub hash: Option<String>,
+    /// `hash` 使用的算法（旧版本的日志中没有记录）
+    #[serde(default)]
+    pub hash_algorithm: Option<HashAlgorithm>,
+    pub before: FileState,
+    pub after: AfterState,
+}
+
+/// 操作日志写入器
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    /// 记录中文件哈希使用的算法
+    hash_algorithm: HashAlgorithm,
+}
+
+impl Journal {
+    /// 默认位置：$XDG_DATA_HOME/dupfinder/journal/journal-<时间>.jsonl
+    pub fn default_path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_DATA_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+            .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))?;
+        let name = format!("journal-{}.jsonl", Local::now().format("%Y%m%d-%H%M%S"));
+        Some(base.join("dupfinder").join("journal").join(name))
+    }
+
+    /// 打开日志文件（已存在时追加），`hash_algorithm` 为记录中文件哈希使用的算法
+    pub fn open(path: &Path, hash_algorithm: HashAlgorithm) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Journal {
+            path: path.to_path_buf(),
+            file,
+            hash_algorithm,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hash_algorithm
+    }
+
+    /// 追加一条记录并立即写入磁盘
+    pub fn append(&mut self, entry: &JournalEntry) -> io::Result<()> {
+        let line = serde_json::to_string(entry)?;
+        writeln!(self.file, "{}", line)?;
+        self.file.sync_data()
+    }
+}
+
+/// 读取日志文件
+fn read_journal(path: &Path) -> io::Result<Vec<JournalEntry>> {
+    let mut entries = Vec::new();
+    for (i, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("第 {} 行格式错误: {}", i + 1, e))
+        })?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// 用一份独立的副本替换链接（先写临时文件再重命名）
+fn replace_with_copy(path: &Path, source: &Path, before: &FileState) -> io::Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(".{}.dupfinder-undo-{}.tmp", name, std::process::id()));
+
+    let result = fs::copy(source, &tmp)
+        .and_then(|_| before.restore_attributes(&tmp))
+        .and_then(|_| fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// 撤销一条记录
+fn undo_entry(entry: &JournalEntry) -> Result<(), String> {
+    match &entry.after {
+        AfterState::Delete => Err("删除操作无法撤销".to_string()),
+        AfterState::HardLink { .. } | AfterState::Reflink { .. } => {
+            // 当前文件与保留的文件共享数据，复制一份独立的内容替换它
+            let current = fs::metadata(&entry.path).map_err(|e| format!("无法读取: {}", e))?;
+            if current.len() != entry.before.size {
+                return Err("文件大小已变化，可能已被修改".to_string());
+            }
+            // 大小相同时内容也可能已被修改，重新计算哈希与记录比较（旧版本日志只能比较大小）
+            if let (Some(hash), Some(algorithm)) = (&entry.hash, entry.hash_algorithm) {
+                let current = hasher::hash_file(&entry.path, algorithm).map_err(|e| format!("无法读取: {}", e))?;
+                if current != *hash {
+                    return Err("文件内容已变化，可能已被修改".to_string());
+                }
+            }
+            replace_with_copy(&entry.path, &entry.path, &entry.before).map_err(|e| e.to_string())
+        }
+        AfterState::Symlink { target } => {
+            match fs::read_link(&entry.path) {
+                Ok(current) if current == *target => {}
+                Ok(_) => return Err("符号链接已被修改".to_string()),
+                Err(e) => return Err(format!("不是符号链接: {}", e)),
+            }
+            let source = fs::metadata(&entry.kept_path)
+                .map_err(|e| format!("保留的文件不可用: {}", e))?;
+            if source.len() != entry.before.size {
+                return Err("保留的文件大小已变化".to_string());
+            }
+            replace_with_copy(&entry.path, &entry.kept_path, &entry.before).map_err(|e| e.to_string())
+        }
+        AfterState::Move { to } => move_file(to, &entry.path).map_err(|e| e.to_string()),
+        AfterState::Trash(trashed) => {
+            move_file(&trashed.files_path, &entry.path).map_err(|e| e.to_string())?;
+            let _ = fs::remove_file(&trashed.info_path);
+            Ok(())
+        }
+    }
+}
+
+/// 按相反的顺序撤销日志中的操作，返回撤销失败的文件数（无法撤销的删除不计入）
+pub fn undo(journal_path: &Path) -> io::Result<usize> {
+    let entries = read_journal(journal_path)?;
+
+    println!(
+        "{}",
+        format!("📜 操作日志: {}（{} 条记录）", journal_path.display(), entries.len()).dimmed()
+    );
+
+    let mut undone = 0;
+    let mut irreversible = Vec::new();
+    let mut failed = 0;
+
+    for entry in entries.iter().rev() {
+        if let AfterState::Delete = entry.after {
+            irreversible.push(entry);
+            continue;
+        }
+
+        match undo_entry(entry) {
+            Ok(()) => {
+                println!("  {} 已撤销 {}", "✓".green(), entry.path.display());
+                undone += 1;
+            }
+            Err(reason) => {
+                println!("  {} 撤销失败 {}: {}", "✗".red(), entry.path.display(), reason);
+                failed += 1;
+            }
+        }
+    }
+
+    if !irreversible.is_empty() {
+        println!("\n{}", "⚠️  以下文件已被删除，无法撤销：".yellow());
+        for entry in &irreversible {
+            println!(
+                "  {} （相同内容保留在 {}）",
+                entry.path.display(),
+                entry.kept_path.display()
+            );
+        }
+    }
+
+    println!("\n{}", "=".repeat(70));
+    println!("{}", "📈 撤销结果:".cyan().bold());
+    println!("  已撤销操作数: {}", undone);
+    if failed > 0 {
+        println!("  {}", format!("撤销失败: {} 个文件", failed).red());
+    }
+    if !irreversible.is_empty() {
+        println!("  {}", format!("无法撤销的删除: {} 个文件", irreversible.len()).yellow());
+    }
+    println!("{}", "=".repeat(70));
+
+    Ok(failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// 记录把 `path` 替换为 `kept` 的硬链接的日志记录
+    fn hard_link_entry(kept: &Path, path: &Path) -> JournalEntry {
+        let before = FileState::capture(path).unwrap();
+        let hash = hasher::hash_file(path, HashAlgorithm::Sha256).unwrap();
+        fs::remove_file(path).unwrap();
+        fs::hard_link(kept, path).unwrap();
+        JournalEntry {
+            timestamp: Local::now().to_rfc3339(),
+            path: path.to_path_buf(),
+            kept_path: kept.to_path_buf(),
+            hash: Some(hash),
+            hash_algorithm: Some(HashAlgorithm::Sha256),
+            before,
+            after: AfterState::HardLink { target: kept.to_path_buf() },
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restore_attributes_sets_mtime_before_read_only_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = crate::test_dir("journal-attributes");
+        let path = dir.join("file");
+        fs::write(&path, "content").unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let state = FileState { size: 7, modified: Some(modified), mode: Some(0o444) };
+
+        state.restore_attributes(&path).unwrap();
+        let metadata = fs::metadata(&path).unwrap();
+        assert_eq!(metadata.modified().unwrap(), modified);
+        assert_eq!(metadata.permissions().mode() & 0o7777, 0o444);
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn undo_hard_link_restores_independent_copy() {
+        let dir = crate::test_dir("journal-undo-hardlink");
+        let (kept, path) = (dir.join("kept"), dir.join("dup"));
+        fs::write(&kept, "same").unwrap();
+        fs::write(&path, "same").unwrap();
+        let entry = hard_link_entry(&kept, &path);
+
+        undo_entry(&entry).unwrap();
+        fs::write(&kept, "edit").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "same");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn undo_hard_link_refuses_modified_content_of_same_size() {
+        let dir = crate::test_dir("journal-undo-modified");
+        let (kept, path) = (dir.join("kept"), dir.join("dup"));
+        fs::write(&kept, "same").unwrap();
+        fs::write(&path, "same").unwrap();
+        let entry = hard_link_entry(&kept, &path);
+
+        // 通过链接修改了内容，大小不变
+        fs::write(&path, "edit").unwrap();
+        assert!(undo_entry(&entry).unwrap_err().contains("内容已变化"));
+        assert!(same_file::is_same_file(&kept, &path).unwrap());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -3,17 +3,40 @@
 // 模仿 fdupes 的 4 层验证流程
 // ============================================================================
 
-use chrono::Local;
-use clap::Parser;
+mod actions;
+mod cache;
+mod filter;
+mod hasher;
+mod interactive;
+mod journal;
+mod quarantine;
+mod quote;
+mod report;
+mod script;
+mod trash;
+mod tui;
+
+use actions::{Action, GroupPlan, LinkKind};
+use cache::{HashCache, HashKind};
+use chrono::{DateTime, Local, SecondsFormat};
+use clap::{Parser, Subcommand, ValueEnum};
 use colored::*;
+use filter::{FilterLogic, IGNORE_FILE_NAME, WalkFilter};
 use globset::{Glob, GlobSet, GlobSetBuilder};
-use regex::Regex;
+use ignore::WalkBuilder;
+use hasher::HashAlgorithm;
+use journal::Journal;
+use report::{DuplicateGroup, DuplicateReport, FileEntry, RootEntry, ScanInfo, Statistics};
 use same_file::is_same_file;
+use script::{PathMap, ScriptFile, ScriptFormat, ScriptGroup, ScriptInfo};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::fs::{self, File};
 use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::SystemTime;
 use walkdir::WalkDir;
 
 // ============================================================================
@@ -27,13 +50,28 @@ use walkdir::WalkDir;
     long_about = "使用多层验证策略快速准确地查找重复文件：\n\
                   1. 文件大小比较\n\
                   2. 部分内容哈希\n\
-                  3. 完整 MD5 校验\n\
-                  4. 逐字节比较"
+                  3. 完整哈希校验（默认 MD5）\n\
+                  4. 逐字节比较",
+    args_conflicts_with_subcommands = true
 )]
 struct Args {
-    /// 要扫描的目录路径
-    #[arg(default_value = ".")]
-    path: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    scan: ScanArgs,
+}
+
+/// 扫描参数（直接扫描与 tui 子命令共用）
+#[derive(clap::Args, Debug)]
+struct ScanArgs {
+    /// 要扫描的目录路径（可指定多个，在所有目录之间查找重复文件）
+    #[arg(default_value = ".", value_name = "PATH")]
+    paths: Vec<PathBuf>,
+
+    /// 参考目录（可多次使用）：参与比较，但其中的文件始终保留，不会被删除
+    #[arg(long = "reference", value_name = "DIR")]
+    references: Vec<PathBuf>,
 
     /// 递归扫描子目录（默认递归）
     #[arg(short, long, default_value_t = true)]
@@ -67,6 +105,48 @@ struct Args {
     #[arg(long, value_name = "FILE")]
     delete_script: Option<PathBuf>,
 
+    /// 删除脚本的格式（默认：Windows 上为 powershell，其他系统为 bash）。
+    /// 脚本只在 --hash md5 或 sha256 时于删除前校验文件哈希，其他算法只检查文件是否存在
+    #[arg(long, value_enum, value_name = "FORMAT", requires = "delete_script")]
+    script_format: Option<ScriptFormat>,
+
+    /// 删除脚本中的路径前缀映射 FROM=TO（可多次指定，使用第一个匹配的），
+    /// 例如 /mnt/share=\\server\share
+    #[arg(long, value_name = "FROM=TO", value_parser = script::parse_path_map, requires = "delete_script")]
+    script_path_map: Vec<PathMap>,
+
+    /// 直接删除重复文件（每组保留一个，删除前再次逐字节确认）
+    #[arg(long, group = "action")]
+    delete: bool,
+
+    /// 用指向保留文件的链接替换重复文件（hard：硬链接，reflink：写时复制克隆，symbolic：符号链接）
+    #[arg(long, value_enum, value_name = "KIND", group = "action")]
+    link: Option<LinkKind>,
+
+    /// 创建符号链接时使用相对路径（配合 --link symbolic）
+    #[arg(long, requires = "link")]
+    relative_symlinks: bool,
+
+    /// 把重复文件移动到隔离目录（保留原始目录结构，并生成恢复清单）
+    #[arg(long, value_name = "DIR", group = "action")]
+    move_to: Option<PathBuf>,
+
+    /// 把重复文件移到桌面回收站（freedesktop.org 规范，可在文件管理器中恢复）
+    #[arg(long, group = "action")]
+    trash: bool,
+
+    /// 演练模式：只显示将要执行的操作，不修改任何文件
+    #[arg(long, requires = "action")]
+    dry_run: bool,
+
+    /// 交互模式：逐组选择要保留的文件，再对其余文件执行所选操作
+    #[arg(short = 'i', long, requires = "action")]
+    interactive: bool,
+
+    /// 操作日志文件（默认 ~/.local/share/dupfinder/journal/journal-<时间>.jsonl），可用 undo 撤销
+    #[arg(long, value_name = "FILE", requires = "action")]
+    journal: Option<PathBuf>,
+
     /// 文件名 glob 模式过滤（可多次使用）
     /// 
     /// 示例:
@@ -85,6 +165,200 @@ struct Args {
     ///   --regex ".*\\.(txt|pdf|doc|docx|xls|xlsx|ppt|pptx|csv|xmind)$"  所有文档
     #[arg(long = "regex", value_name = "REGEX")]
     regex_pattern: Option<String>,
+
+    /// -p 和 --regex 匹配相对于扫描目录的路径，而不只是文件名；
+    /// 此时 glob 中的 * 不匹配 /，用 ** 匹配任意层目录
+    ///
+    /// 示例:
+    ///   --match-path -p "**/Camera/*.jpg"
+    ///   --match-path --regex "^photos/2023/"
+    #[arg(long)]
+    match_path: bool,
+
+    /// 同时指定 -p 和 --regex 时的组合方式：or 任一匹配（默认），and 两者都匹配
+    #[arg(long, value_enum, default_value_t = FilterLogic::Or, value_name = "LOGIC")]
+    filter_logic: FilterLogic,
+
+    /// 排除匹配该 glob 的文件和目录（可多次使用），匹配相对于扫描目录的路径，
+    /// 被排除的目录不会被遍历；* 不匹配 /，不含 / 的模式在任意深度匹配，以 / 结尾的模式只匹配目录
+    ///
+    /// 示例:
+    ///   --exclude .git --exclude node_modules --exclude @eaDir
+    ///   --exclude "*.tmp" --exclude "build/cache"
+    #[arg(long = "exclude", value_name = "GLOB")]
+    excludes: Vec<String>,
+
+    /// 排除相对路径匹配该正则表达式的文件和目录（可多次使用，路径分隔符为 /）
+    ///
+    /// 示例:
+    ///   --exclude-regex "(^|/)\\.[^/]+$"            所有隐藏文件和目录
+    ///   --exclude-regex "^photos/20(1[0-9])/"        2010-2019 年的照片目录
+    #[arg(long = "exclude-regex", value_name = "REGEX")]
+    exclude_regexes: Vec<String>,
+
+    /// 只检测不小于该大小的文件，如 10K、1.5MiB、2G（K/M/G 均按 1024 计算）
+    #[arg(long, value_name = "SIZE", value_parser = filter::parse_size)]
+    min_size: Option<u64>,
+
+    /// 只检测不大于该大小的文件，如 100M、4GiB
+    #[arg(long, value_name = "SIZE", value_parser = filter::parse_size)]
+    max_size: Option<u64>,
+
+    /// 包含空文件（默认跳过，所有空文件内容都相同）
+    #[arg(long)]
+    include_empty: bool,
+
+    /// 只检测修改时间早于该时间的文件：时长（30d、12h、1w2d，单位 s/m/h/d/w）或日期（2024-01-31、2024-01-31 08:00）
+    #[arg(long, value_name = "TIME", value_parser = filter::parse_time)]
+    older_than: Option<SystemTime>,
+
+    /// 只检测修改时间不早于该时间的文件，格式同 --older-than
+    #[arg(long, value_name = "TIME", value_parser = filter::parse_time)]
+    newer_than: Option<SystemTime>,
+
+    /// 遵守 .gitignore、.ignore 和 .dupfinderignore 中的规则（完整的 gitignore 语法，
+    /// 包括 ! 取反、/ 开头的锚定模式和子目录中的规则文件），跳过构建产物和缓存；
+    /// 同时跳过 .git 目录
+    #[arg(long)]
+    respect_ignore: bool,
+
+    /// 并行计算哈希的线程数（默认使用全部 CPU 核心）
+    #[arg(short = 'j', long, value_name = "N")]
+    threads: Option<usize>,
+
+    /// 哈希算法（md5 / sha256 / blake3 / xxh3）
+    #[arg(long = "hash", value_enum, default_value_t = HashAlgorithm::Md5, value_name = "ALGORITHM")]
+    hash_algorithm: HashAlgorithm,
+
+    /// 每组保留哪个文件（其余文件出现在删除脚本中）
+    #[arg(long = "keep", value_enum, default_value_t = KeepPolicy::First, value_name = "POLICY")]
+    keep_policy: KeepPolicy,
+
+    /// 优先保留匹配该 glob 的文件（可多次使用，越靠前优先级越高），匹配完整路径
+    ///
+    /// 示例:
+    ///   --keep-path-priority "/archive/**" --keep-path-priority "**/originals/**"
+    #[arg(long = "keep-path-priority", value_name = "GLOB")]
+    keep_path_priority: Vec<String>,
+
+    /// 哈希缓存文件（默认 ~/.cache/dupfinder/hashes.db）
+    #[arg(long, value_name = "FILE")]
+    cache: Option<PathBuf>,
+
+    /// 不使用哈希缓存
+    #[arg(long, conflicts_with_all = ["cache", "cache_prune"])]
+    no_cache: bool,
+
+    /// 扫描结束后清理缓存中已失效的记录
+    #[arg(long)]
+    cache_prune: bool,
+}
+
+/// 子命令
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// 根据恢复清单把隔离的文件放回原位置
+    Restore {
+        /// --move-to 生成的清单文件（manifest-*.jsonl）
+        #[arg(value_name = "MANIFEST")]
+        manifest: PathBuf,
+    },
+    /// 根据操作日志撤销链接、隔离和回收站操作（删除操作无法撤销）
+    Undo {
+        /// 执行操作时生成的日志文件（journal-*.jsonl）
+        #[arg(value_name = "JOURNAL")]
+        journal: PathBuf,
+    },
+    /// 全屏终端界面：浏览重复文件组，标记文件后执行删除或链接操作
+    Tui(Box<ScanArgs>),
+    /// 根据（审阅过的）JSON 报告处理重复文件，处理前重新校验每个文件
+    Apply {
+        /// --json 导出的报告文件；"keep": true 的文件保留，其余文件处理
+        #[arg(value_name = "REPORT")]
+        report: PathBuf,
+
+        /// 对未保留的文件执行的操作
+        #[arg(long, value_enum, value_name = "ACTION")]
+        action: ReportAction,
+
+        /// 链接方式（配合 --action link）
+        #[arg(long = "link-kind", value_enum, default_value_t = LinkKind::Hard, value_name = "KIND")]
+        link_kind: LinkKind,
+
+        /// 创建符号链接时使用相对路径
+        #[arg(long)]
+        relative_symlinks: bool,
+
+        /// 隔离目录（配合 --action move）
+        #[arg(long, value_name = "DIR", required_if_eq("action", "move"))]
+        move_to: Option<PathBuf>,
+
+        /// 演练模式：只校验并显示将要执行的操作
+        #[arg(long)]
+        dry_run: bool,
+
+        /// 操作日志文件
+        #[arg(long, value_name = "FILE")]
+        journal: Option<PathBuf>,
+    },
+}
+
+/// apply 子命令可执行的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ReportAction {
+    /// 删除
+    Delete,
+    /// 用链接替换（方式由 --link-kind 指定）
+    Link,
+    /// 移动到隔离目录（--move-to 指定）
+    Move,
+}
+
+/// 选择每组中保留哪个文件的策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum KeepPolicy {
+    /// 扫描顺序中的第一个（按扫描目录顺序，再按路径排序）
+    First,
+    /// 修改时间最早的
+    Oldest,
+    /// 修改时间最新的
+    Newest,
+    /// 路径最短的
+    ShortestPath,
+    /// 路径最长的
+    LongestPath,
+    /// 目录层级最浅的
+    Shallowest,
+    /// 目录层级最深的
+    Deepest,
+    /// 路径按字母顺序最靠前的
+    Alphabetical,
+}
+
+impl KeepPolicy {
+    /// 比较两个文件，排在前面的优先保留
+    fn compare(self, a: &FileInfo, b: &FileInfo) -> std::cmp::Ordering {
+        let path_len = |f: &FileInfo| f.path.as_os_str().len();
+        let depth = |f: &FileInfo| f.path.components().count();
+
+        match self {
+            KeepPolicy::First => std::cmp::Ordering::Equal,
+            // 无法获取修改时间的文件排在最后
+            KeepPolicy::Oldest => match (a.modified, b.modified) {
+                (Some(x), Some(y)) => x.cmp(&y),
+                (x, y) => x.is_none().cmp(&y.is_none()),
+            },
+            KeepPolicy::Newest => match (a.modified, b.modified) {
+                (Some(x), Some(y)) => y.cmp(&x),
+                (x, y) => x.is_none().cmp(&y.is_none()),
+            },
+            KeepPolicy::ShortestPath => path_len(a).cmp(&path_len(b)),
+            KeepPolicy::LongestPath => path_len(b).cmp(&path_len(a)),
+            KeepPolicy::Shallowest => depth(a).cmp(&depth(b)),
+            KeepPolicy::Deepest => depth(b).cmp(&depth(a)),
+            KeepPolicy::Alphabetical => a.path.as_os_str().cmp(b.path.as_os_str()),
+        }
+    }
 }
 
 // ============================================================================
@@ -94,21 +368,85 @@ struct Args {
 struct FileInfo {
     path: PathBuf,
     size: u64,
+    root: usize,                   // 所属扫描根目录的下标
+    is_reference: bool,            // 是否位于参考目录中（始终保留）
+    modified: Option<SystemTime>,  // 修改时间
     partial_hash: Option<String>,  // 部分内容的哈希
     full_hash: Option<String>,     // 完整文件的哈希
 }
 
 impl FileInfo {
-    fn new(path: PathBuf, size: u64) -> Self {
+    fn new(path: PathBuf, metadata: &fs::Metadata, root: usize, is_reference: bool) -> Self {
         FileInfo {
             path,
-            size,
+            size: metadata.len(),
+            root,
+            is_reference,
+            modified: metadata.modified().ok(),
             partial_hash: None,
             full_hash: None,
         }
     }
 }
 
+/// 扫描根目录
+#[derive(Debug, Clone)]
+struct ScanRoot {
+    path: PathBuf,    // 规范化后的绝对路径
+    label: String,    // 输出中使用的标签（R1、R2 ...）
+    reference: bool,  // 是否位于参考目录中
+}
+
+/// 规范化路径列表，无法访问的路径给出警告后跳过
+fn canonicalize_all(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut result: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        match path.canonicalize() {
+            Ok(abs) => {
+                if !result.contains(&abs) {
+                    result.push(abs);
+                }
+            }
+            Err(e) => {
+                eprintln!("{} {}: {}", "⚠️  无法访问扫描路径".yellow(), path.display(), e);
+            }
+        }
+    }
+    result
+}
+
+/// 规范化扫描路径并去掉重叠的根目录：
+/// 重复的路径只保留一个；递归扫描时，位于其他根目录之下的路径会被合并。
+/// 参考目录同样作为扫描根目录参与比较
+fn resolve_roots(paths: &[PathBuf], references: &[PathBuf], recursive: bool) -> Vec<ScanRoot> {
+    let mut candidates = canonicalize_all(paths);
+    for reference in references {
+        if !candidates.contains(reference) {
+            candidates.push(reference.clone());
+        }
+    }
+
+    let mut roots: Vec<ScanRoot> = Vec::new();
+    for path in &candidates {
+        if recursive {
+            if let Some(parent) = candidates.iter().find(|other| *other != path && path.starts_with(other)) {
+                println!(
+                    "{}",
+                    format!("↪ {} 已包含在 {} 中，跳过", path.display(), parent.display()).dimmed()
+                );
+                continue;
+            }
+        }
+        roots.push(ScanRoot {
+            path: path.clone(),
+            label: format!("R{}", roots.len() + 1),
+            reference: references.iter().any(|r| path.starts_with(r)),
+        });
+    }
+
+    roots
+}
+
 // ============================================================================
 // 【Rust 概念 3: 主逻辑结构】
 // ============================================================================
@@ -117,66 +455,56 @@ struct DupFinder {
     show_size: bool,
     include_hardlinks: bool,
     relative_path: bool,
-    base_path: PathBuf,
-    glob_set: Option<GlobSet>,
-    regex: Option<Regex>,
+    roots: Vec<ScanRoot>,
+    references: Vec<PathBuf>,
+    filter: WalkFilter,
+    respect_ignore: bool,
+    keep_policy: KeepPolicy,
+    keep_priority: Option<GlobSet>,
+    threads: usize,
+    hash_algorithm: HashAlgorithm,
+    cache: Option<HashCache>,
 }
 
 impl DupFinder {
     fn new(
-        verbose: bool,
-        show_size: bool,
-        include_hardlinks: bool,
-        relative_path: bool,
-        base_path: PathBuf,
-        glob_set: Option<GlobSet>,
-        regex: Option<Regex>,
+        args: &ScanArgs,
+        roots: Vec<ScanRoot>,
+        references: Vec<PathBuf>,
+        filter: WalkFilter,
+        keep_priority: Option<GlobSet>,
+        cache: Option<HashCache>,
     ) -> Self {
+        // 未指定线程数时使用可用的 CPU 核心数
+        let threads = args.threads.unwrap_or_else(|| {
+            thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        });
+
         DupFinder {
-            verbose,
-            show_size,
-            include_hardlinks,
-            relative_path,
-            base_path,
-            glob_set,
-            regex,
+            verbose: args.verbose,
+            show_size: args.size,
+            include_hardlinks: args.hardlinks,
+            relative_path: args.relative_path,
+            roots,
+            references,
+            filter,
+            respect_ignore: args.respect_ignore,
+            keep_policy: args.keep_policy,
+            keep_priority,
+            threads: threads.max(1),
+            hash_algorithm: args.hash_algorithm,
+            cache,
         }
     }
     
-    /// 检查文件是否应该被包含在扫描中
-    fn should_include_file(&self, path: &Path) -> bool {
-        // 如果没有指定任何过滤条件，包含所有文件
-        if self.glob_set.is_none() && self.regex.is_none() {
-            return true;
-        }
-        
-        let filename = match path.file_name().and_then(|n| n.to_str()) {
-            Some(name) => name,
-            None => return false,
-        };
-        
-        // 检查 glob 模式
-        if let Some(ref globset) = self.glob_set {
-            if globset.is_match(filename) {
-                return true;
-            }
-        }
-        
-        // 检查正则表达式
-        if let Some(ref regex) = self.regex {
-            if regex.is_match(filename) {
-                return true;
-            }
-        }
-        
-        false
-    }
-    
     /// 格式化路径显示（绝对路径或相对路径）
-    fn format_path(&self, path: &Path) -> String {
+    fn format_path(&self, file: &FileInfo) -> String {
+        let path = file.path.as_path();
         if self.relative_path {
-            // 显示相对于扫描目录的路径
-            if let Ok(rel_path) = path.strip_prefix(&self.base_path) {
+            // 显示相对于所属扫描目录的路径
+            if let Ok(rel_path) = path.strip_prefix(&self.roots[file.root].path) {
                 format!("./{}", rel_path.display())
             } else {
                 path.display().to_string()
@@ -191,31 +519,113 @@ impl DupFinder {
         }
     }
 
+    /// 扫描多个目录时返回文件所属根目录的标签，单个目录时不显示
+    fn root_label(&self, file: &FileInfo) -> Option<&str> {
+        if self.roots.len() > 1 {
+            Some(&self.roots[file.root].label)
+        } else {
+            None
+        }
+    }
+
+    /// 脚本注释中使用的根目录标签后缀
+    fn label_suffix(&self, file: &FileInfo) -> String {
+        self.root_label(file)
+            .map(|label| format!(" [{}]", label))
+            .unwrap_or_default()
+    }
+
+    /// 所有扫描根目录的描述（用于报告和脚本头部）
+    fn roots_description(&self) -> String {
+        if self.roots.len() == 1 {
+            let suffix = if self.roots[0].reference { "（参考）" } else { "" };
+            return format!("{}{}", self.roots[0].path.display(), suffix);
+        }
+        self.roots
+            .iter()
+            .map(|root| {
+                let suffix = if root.reference { "（参考）" } else { "" };
+                format!("[{}] {}{}", root.label, root.path.display(), suffix)
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    // ========================================================================
+    // 并行哈希：工作线程池
+    // ========================================================================
+    /// 使用 `threads` 个工作线程并行计算哈希，返回结果与输入顺序一一对应，
+    /// 因此后续分组结果与单线程运行完全一致
+    fn hash_parallel<F>(&self, files: &[FileInfo], hash_fn: F) -> Vec<io::Result<String>>
+    where
+        F: Fn(&Path) -> io::Result<String> + Sync,
+    {
+        let workers = self.threads.min(files.len());
+        if workers <= 1 {
+            return files.iter().map(|f| hash_fn(&f.path)).collect();
+        }
+
+        // 每个线程从共享计数器领取下一个待处理的文件
+        let next = AtomicUsize::new(0);
+        let mut results: Vec<Option<io::Result<String>>> = files.iter().map(|_| None).collect();
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let i = next.fetch_add(1, Ordering::Relaxed);
+                            if i >= files.len() {
+                                break;
+                            }
+                            done.push((i, hash_fn(&files[i].path)));
+                        }
+                        done
+                    })
+                })
+                .collect();
+
+            for handle in handles {
+                for (i, result) in handle.join().expect("哈希工作线程异常退出") {
+                    results[i] = Some(result);
+                }
+            }
+        });
+
+        results
+            .into_iter()
+            .map(|r| r.expect("每个文件都应被处理"))
+            .collect()
+    }
+
+    /// 启用缓存时先查缓存，未命中再计算
+    fn cached_hash<F>(&self, path: &Path, kind: HashKind, compute: F) -> io::Result<String>
+    where
+        F: FnOnce() -> io::Result<String>,
+    {
+        match self.cache {
+            Some(ref cache) => cache.get_or_compute(path, kind, compute),
+            None => compute(),
+        }
+    }
+
     // ========================================================================
     // 第 1 层：按文件大小分组
     // ========================================================================
-    fn group_by_size(&self, paths: Vec<PathBuf>) -> HashMap<u64, Vec<FileInfo>> {
+    fn group_by_size(&self, files: Vec<FileInfo>) -> BTreeMap<u64, Vec<FileInfo>> {
         if self.verbose {
             println!("{}", "🔍 第 1 层：按文件大小分组...".cyan());
         }
 
-        let mut size_groups: HashMap<u64, Vec<FileInfo>> = HashMap::new();
-
-        for path in paths {
-            if let Ok(metadata) = fs::metadata(&path) {
-                let size = metadata.len();
-                
-                // 跳过空文件
-                if size == 0 {
-                    continue;
-                }
+        let mut size_groups: BTreeMap<u64, Vec<FileInfo>> = BTreeMap::new();
 
-                let file_info = FileInfo::new(path, size);
-                size_groups
-                    .entry(size)
-                    .or_insert_with(Vec::new)
-                    .push(file_info);
-            }
+        // 空文件和超出 --min-size / --max-size 的文件在遍历时已经跳过
+        for file_info in files {
+            size_groups
+                .entry(file_info.size)
+                .or_default()
+                .push(file_info);
         }
 
         // 只保留大小相同的文件（潜在重复）
@@ -242,31 +652,32 @@ impl DupFinder {
         let bytes_read = file.read(&mut buffer)?;
         buffer.truncate(bytes_read);
 
-        let digest = md5::compute(&buffer);
-        Ok(format!("{:x}", digest))
+        let mut hasher = self.hash_algorithm.hasher();
+        hasher.update(&buffer);
+        Ok(hasher.finish())
     }
 
     fn group_by_partial_hash(
         &self,
-        size_groups: HashMap<u64, Vec<FileInfo>>,
-    ) -> HashMap<String, Vec<FileInfo>> {
+        size_groups: BTreeMap<u64, Vec<FileInfo>>,
+    ) -> BTreeMap<String, Vec<FileInfo>> {
         if self.verbose {
             println!("{}", "🔍 第 2 层：计算部分内容哈希...".cyan());
         }
 
-        let mut hash_groups: HashMap<String, Vec<FileInfo>> = HashMap::new();
+        let mut hash_groups: BTreeMap<String, Vec<FileInfo>> = BTreeMap::new();
         let mut checked = 0;
 
-        for (_size, mut files) in size_groups {
-            for file_info in &mut files {
-                if let Ok(hash) = self.calculate_partial_hash(&file_info.path) {
-                    file_info.partial_hash = Some(hash.clone());
-                    hash_groups
-                        .entry(hash)
-                        .or_insert_with(Vec::new)
-                        .push(file_info.clone());
-                    checked += 1;
-                }
+        let files: Vec<FileInfo> = size_groups.into_values().flatten().collect();
+        let hashes = self.hash_parallel(&files, |path| {
+            self.cached_hash(path, HashKind::Partial, || self.calculate_partial_hash(path))
+        });
+
+        for (mut file_info, hash) in files.into_iter().zip(hashes) {
+            if let Ok(hash) = hash {
+                file_info.partial_hash = Some(hash.clone());
+                hash_groups.entry(hash).or_default().push(file_info);
+                checked += 1;
             }
         }
 
@@ -287,45 +698,36 @@ impl DupFinder {
     }
 
     // ========================================================================
-    // 第 3 层：计算完整文件 MD5
+    // 第 3 层：计算完整文件哈希
     // ========================================================================
     fn calculate_full_hash(&self, path: &Path) -> io::Result<String> {
-        let mut file = File::open(path)?;
-        let mut context = md5::Context::new();
-        let mut buffer = vec![0u8; 8192];
-
-        loop {
-            let bytes_read = file.read(&mut buffer)?;
-            if bytes_read == 0 {
-                break;
-            }
-            context.consume(&buffer[..bytes_read]);
-        }
-
-        Ok(format!("{:x}", context.compute()))
+        hasher::hash_file(path, self.hash_algorithm)
     }
 
     fn group_by_full_hash(
         &self,
-        partial_groups: HashMap<String, Vec<FileInfo>>,
-    ) -> HashMap<String, Vec<FileInfo>> {
+        partial_groups: BTreeMap<String, Vec<FileInfo>>,
+    ) -> BTreeMap<String, Vec<FileInfo>> {
         if self.verbose {
-            println!("{}", "🔍 第 3 层：计算完整文件 MD5...".cyan());
+            println!(
+                "{}",
+                format!("🔍 第 3 层：计算完整文件哈希（{}）...", self.hash_algorithm.name()).cyan()
+            );
         }
 
-        let mut full_hash_groups: HashMap<String, Vec<FileInfo>> = HashMap::new();
+        let mut full_hash_groups: BTreeMap<String, Vec<FileInfo>> = BTreeMap::new();
         let mut checked = 0;
 
-        for (_partial, mut files) in partial_groups {
-            for file_info in &mut files {
-                if let Ok(hash) = self.calculate_full_hash(&file_info.path) {
-                    file_info.full_hash = Some(hash.clone());
-                    full_hash_groups
-                        .entry(hash)
-                        .or_insert_with(Vec::new)
-                        .push(file_info.clone());
-                    checked += 1;
-                }
+        let files: Vec<FileInfo> = partial_groups.into_values().flatten().collect();
+        let hashes = self.hash_parallel(&files, |path| {
+            self.cached_hash(path, HashKind::Full, || self.calculate_full_hash(path))
+        });
+
+        for (mut file_info, hash) in files.into_iter().zip(hashes) {
+            if let Ok(hash) = hash {
+                file_info.full_hash = Some(hash.clone());
+                full_hash_groups.entry(hash).or_default().push(file_info);
+                checked += 1;
             }
         }
 
@@ -335,7 +737,7 @@ impl DupFinder {
         if self.verbose {
             let potential = full_hash_groups.values().map(|v| v.len()).sum::<usize>();
             println!(
-                "  ✓ 检查了 {} 个文件，找到 {} 组完整 MD5 相同（{} 个文件）",
+                "  ✓ 检查了 {} 个文件，找到 {} 组完整哈希相同（{} 个文件）",
                 checked,
                 full_hash_groups.len(),
                 potential
@@ -348,48 +750,32 @@ impl DupFinder {
     // ========================================================================
     // 第 4 层：逐字节比较（最终确认）
     // ========================================================================
-    fn byte_compare(&self, path1: &Path, path2: &Path) -> io::Result<bool> {
-        // 检查是否是硬链接（同一个文件）
-        // 硬链接指向同一个 inode，删除一个不会节省空间
-        if !self.include_hardlinks && is_same_file(path1, path2).unwrap_or(false) {
-            if self.verbose {
-                println!(
-                    "  {} 跳过硬链接: {} <-> {}",
-                    "↪".dimmed(),
-                    path1.display(),
-                    path2.display()
-                );
-            }
-            return Ok(false);  // 不算重复
+    /// 检查文件是否是某个已归类文件的硬链接（同一个文件）
+    /// 硬链接指向同一个 inode，删除一个不会节省空间
+    fn is_hardlink_of(&self, class: &[FileInfo], path: &Path) -> bool {
+        if self.include_hardlinks {
+            return false;
         }
 
-        let mut file1 = File::open(path1)?;
-        let mut file2 = File::open(path2)?;
-
-        let mut buffer1 = vec![0u8; 8192];
-        let mut buffer2 = vec![0u8; 8192];
-
-        loop {
-            let bytes1 = file1.read(&mut buffer1)?;
-            let bytes2 = file2.read(&mut buffer2)?;
-
-            if bytes1 != bytes2 {
-                return Ok(false);
-            }
-
-            if bytes1 == 0 {
-                return Ok(true);
-            }
-
-            if buffer1[..bytes1] != buffer2[..bytes2] {
-                return Ok(false);
+        match class.iter().find(|f| is_same_file(&f.path, path).unwrap_or(false)) {
+            Some(original) => {
+                if self.verbose {
+                    println!(
+                        "  {} 跳过硬链接: {} <-> {}",
+                        "↪".dimmed(),
+                        original.path.display(),
+                        path.display()
+                    );
+                }
+                true
             }
+            None => false,
         }
     }
 
     fn verify_duplicates(
         &self,
-        hash_groups: HashMap<String, Vec<FileInfo>>,
+        hash_groups: BTreeMap<String, Vec<FileInfo>>,
     ) -> Vec<Vec<FileInfo>> {
         if self.verbose {
             println!("{}", "🔍 第 4 层：逐字节比较验证...".cyan());
@@ -399,19 +785,27 @@ impl DupFinder {
         let mut comparisons = 0;
 
         for (_hash, files) in hash_groups {
-            // 使用图的方式验证：如果 A == B 且 B == C，则 A == B == C
-            let mut duplicate_group = vec![files[0].clone()];
+            // 将哈希相同的文件划分为若干等价类（与 fdupes 相同）：
+            // 每个文件依次与已有各类的代表比较，相同则加入该类，否则自成一类。
+            // 这样即使哈希碰撞或文件在两层之间被修改，其余相同的文件也不会被遗漏
+            let mut classes: Vec<Vec<FileInfo>> = Vec::new();
+
+            'files: for file_info in files {
+                for class in classes.iter_mut() {
+                    if self.is_hardlink_of(class, &file_info.path) {
+                        continue 'files;
+                    }
 
-            for i in 1..files.len() {
-                if let Ok(true) = self.byte_compare(&files[0].path, &files[i].path) {
-                    duplicate_group.push(files[i].clone());
                     comparisons += 1;
+                    if let Ok(true) = byte_compare(&class[0].path, &file_info.path) {
+                        class.push(file_info);
+                        continue 'files;
+                    }
                 }
+                classes.push(vec![file_info]);
             }
 
-            if duplicate_group.len() > 1 {
-                verified_groups.push(duplicate_group);
-            }
+            verified_groups.extend(classes.into_iter().filter(|class| class.len() > 1));
         }
 
         if self.verbose {
@@ -430,7 +824,8 @@ impl DupFinder {
     // ========================================================================
     // 显示结果
     // ========================================================================
-    fn display_results(&self, groups: &[Vec<FileInfo>]) {
+    /// 输出重复文件组和统计信息；指定了内置操作时，按操作说明预计的空间（删除释放或链接回收）
+    fn display_results(&self, groups: &[Vec<FileInfo>], action: Option<&Action>) {
         if groups.is_empty() {
             println!("{}", "✅ 未发现重复文件".green());
             return;
@@ -450,26 +845,38 @@ impl DupFinder {
                 );
             }
 
-            for file_info in group {
-                let path_display = self.format_path(&file_info.path);
-                println!("  {}", path_display);
+            for (j, file_info) in group.iter().enumerate() {
+                let mut path_display = self.format_path(file_info);
+                if j == 0 {
+                    path_display = format!("{} {}", path_display, "(保留)".green());
+                }
+                if file_info.is_reference {
+                    path_display = format!("{} {}", path_display, "(参考)".dimmed());
+                }
+                match self.root_label(file_info) {
+                    Some(label) => println!("  {} {}", format!("[{}]", label).dimmed(), path_display),
+                    None => println!("  {}", path_display),
+                }
             }
         }
 
         let total_files: usize = groups.iter().map(|g| g.len()).sum();
-        let can_save: usize = groups.iter().map(|g| g.len() - 1).sum();
+        let can_save = deletable_count(groups);
         
         println!("\n{}", "=".repeat(70));
-        println!("{}", format!("📈 统计信息:").cyan().bold());
+        println!("{}", "📈 统计信息:".cyan().bold());
         println!("  总重复文件数: {}", total_files);
-        println!("  可删除文件数: {} (保留每组 1 个)", can_save);
+        if self.references.is_empty() {
+            println!("  可删除文件数: {} (保留每组 1 个)", can_save);
+        } else {
+            println!("  可删除文件数: {} (保留每组 1 个及参考目录中的文件)", can_save);
+        }
         
         if self.show_size {
-            let total_size: u64 = groups.iter()
-                .map(|g| g[0].size * (g.len() as u64 - 1))
-                .sum();
+            let total_size = reclaimable_size(groups);
             println!(
-                "  可节省空间: {} ({} bytes)",
+                "  {}: {} ({} bytes)",
+                action.map_or("可节省空间", Action::space_estimate_label),
                 format_size(total_size),
                 total_size
             );
@@ -481,43 +888,6 @@ impl DupFinder {
     // JSON 输出
     // ========================================================================
     fn export_json(&self, groups: &[Vec<FileInfo>], output_path: &Path) -> io::Result<()> {
-        // 构建 JSON 数据结构
-        #[derive(Serialize)]
-        struct DuplicateReport {
-            scan_info: ScanInfo,
-            duplicate_groups: Vec<DuplicateGroup>,
-            statistics: Statistics,
-        }
-
-        #[derive(Serialize)]
-        struct ScanInfo {
-            base_path: String,
-            total_groups: usize,
-            timestamp: String,
-        }
-
-        #[derive(Serialize)]
-        struct DuplicateGroup {
-            group_id: usize,
-            file_size: u64,
-            file_count: usize,
-            md5_hash: Option<String>,
-            files: Vec<FileEntry>,
-        }
-
-        #[derive(Serialize)]
-        struct FileEntry {
-            path: String,
-            absolute_path: String,
-        }
-
-        #[derive(Serialize)]
-        struct Statistics {
-            total_duplicate_files: usize,
-            deletable_files: usize,
-            potential_space_savings: u64,
-        }
-
         // 准备数据
         let duplicate_groups: Vec<DuplicateGroup> = groups
             .iter()
@@ -525,8 +895,9 @@ impl DupFinder {
             .map(|(i, group)| {
                 let files = group
                     .iter()
-                    .map(|f| {
-                        let path_display = self.format_path(&f.path);
+                    .enumerate()
+                    .map(|(j, f)| {
+                        let path_display = self.format_path(f);
                         let abs_path = f.path
                             .canonicalize()
                             .unwrap_or_else(|_| f.path.clone())
@@ -535,6 +906,12 @@ impl DupFinder {
                         FileEntry {
                             path: path_display,
                             absolute_path: abs_path,
+                            root: self.roots[f.root].label.clone(),
+                            modified: f
+                                .modified
+                                .map(|t| DateTime::<Local>::from(t).to_rfc3339_opts(SecondsFormat::Secs, false)),
+                            reference: f.is_reference,
+                            keep: j == 0 || f.is_reference,
                         }
                     })
                     .collect();
@@ -543,22 +920,34 @@ impl DupFinder {
                     group_id: i + 1,
                     file_size: group[0].size,
                     file_count: group.len(),
-                    md5_hash: group[0].full_hash.clone(),
+                    hash: group[0].full_hash.clone(),
                     files,
                 }
             })
             .collect();
 
         let total_files: usize = groups.iter().map(|g| g.len()).sum();
-        let deletable: usize = groups.iter().map(|g| g.len() - 1).sum();
-        let space_savings: u64 = groups
-            .iter()
-            .map(|g| g[0].size * (g.len() as u64 - 1))
-            .sum();
+        let deletable = deletable_count(groups);
+        let space_savings = reclaimable_size(groups);
 
         let report = DuplicateReport {
             scan_info: ScanInfo {
-                base_path: self.base_path.display().to_string(),
+                base_path: self.roots[0].path.display().to_string(),
+                roots: self
+                    .roots
+                    .iter()
+                    .map(|root| RootEntry {
+                        label: root.label.clone(),
+                        path: root.path.display().to_string(),
+                        reference: root.reference,
+                    })
+                    .collect(),
+                hash_algorithm: self.hash_algorithm,
+                keep_policy: self
+                    .keep_policy
+                    .to_possible_value()
+                    .map(|v| v.get_name().to_string())
+                    .unwrap_or_default(),
                 total_groups: groups.len(),
                 timestamp: Local::now().to_rfc3339(),
             },
@@ -587,16 +976,41 @@ impl DupFinder {
     // ========================================================================
     // 生成删除脚本
     // ========================================================================
-    fn generate_delete_script(&self, groups: &[Vec<FileInfo>], output_path: &Path) -> io::Result<()> {
-        // 运行时检测操作系统，决定生成哪种脚本
-        // 使用 std::env::consts::OS 而不是编译时 cfg
-        let is_windows = std::env::consts::OS == "windows";
+    fn generate_delete_script(
+        &self,
+        groups: &[Vec<FileInfo>],
+        output_path: &Path,
+        format: ScriptFormat,
+        path_map: &[PathMap],
+    ) -> io::Result<()> {
+        // 脚本中使用规范化后的绝对路径，再按 --script-path-map 换成执行端的路径
+        let script_file = |file: &FileInfo| {
+            let path = file.path.canonicalize().unwrap_or_else(|_| file.path.clone());
+            ScriptFile {
+                path: script::map_path(path_map, path.as_os_str()),
+                size: file.size,
+                label: self.label_suffix(file),
+            }
+        };
 
-        let script = if is_windows {
-            self.generate_powershell_script(groups, output_path)?
-        } else {
-            self.generate_bash_script(groups, output_path)?
+        let info = ScriptInfo {
+            roots: self.roots_description(),
+            has_references: !self.references.is_empty(),
+            output_path: output_path.to_path_buf(),
+            hash_algorithm: self.hash_algorithm,
+            groups: groups
+                .iter()
+                .map(|group| ScriptGroup {
+                    size: group[0].size,
+                    file_count: group.len(),
+                    hash: group[0].full_hash.clone(),
+                    keep: script_file(&group[0]),
+                    references: group.iter().skip(1).filter(|f| f.is_reference).map(script_file).collect(),
+                    targets: removable_files(group).map(script_file).collect(),
+                })
+                .collect(),
         };
+        let script = script::render(format, &info);
 
         // 写入文件
         let mut file = File::create(output_path)?;
@@ -604,7 +1018,7 @@ impl DupFinder {
 
         // 设置执行权限（Unix 系统）
         #[cfg(unix)]
-        {
+        if format.executable() {
             use std::os::unix::fs::PermissionsExt;
             let mut perms = fs::metadata(output_path)?.permissions();
             perms.set_mode(0o755);
@@ -616,277 +1030,145 @@ impl DupFinder {
             "✅ 删除脚本已生成:".green(),
             output_path.display()
         );
-        
-        if is_windows {
-            println!("{}", "   请仔细检查后执行！".yellow());
-            println!("{}", "   执行方式：".cyan());
-            println!("{}", "     PowerShell -ExecutionPolicy Bypass -File <脚本文件>".cyan());
-            println!("{}", "     或右键脚本 -> 使用 PowerShell 运行".cyan());
-        } else {
-            println!("{}", "   请仔细检查后执行！".yellow());
-            println!("{}", "   执行方式：bash <脚本文件>".cyan());
+        println!("{}", "   请仔细检查后执行！".yellow());
+        for line in format.run_hint() {
+            println!("   {}", line.cyan());
+        }
+        if !info.verifies() {
+            println!(
+                "{}",
+                format!(
+                    "   ⚠️  脚本无法校验 {} 哈希（只支持 md5 和 sha256），删除前只检查文件是否存在",
+                    self.hash_algorithm.name()
+                )
+                .yellow()
+            );
         }
 
         Ok(())
     }
 
     // ========================================================================
-    // 生成 Bash 脚本（Linux/macOS）
+    // 遍历扫描目录
     // ========================================================================
-    fn generate_bash_script(&self, groups: &[Vec<FileInfo>], output_path: &Path) -> io::Result<String> {
-        let mut script = String::new();
-
-        // 脚本头部
-        script.push_str("#!/bin/bash\n");
-        script.push_str("# ============================================================================\n");
-        script.push_str("# DupFinder 自动生成的删除脚本\n");
-        script.push_str(&format!("# 生成时间: {}\n", Local::now().format("%Y-%m-%d %H:%M:%S")));
-        script.push_str(&format!("# 扫描路径: {}\n", self.base_path.display()));
-        script.push_str(&format!("# 重复组数: {}\n", groups.len()));
-        script.push_str("# ============================================================================\n");
-        script.push_str("#\n");
-        script.push_str("# ⚠️  警告：此脚本将删除重复文件！\n");
-        script.push_str("#    每组重复文件会保留第一个，删除其他的。\n");
-        script.push_str("#    请仔细检查后再执行！\n");
-        script.push_str("#\n");
-        script.push_str("# 使用方法:\n");
-        script.push_str("#   1. 仔细检查下面的删除命令\n");
-        script.push_str("#   2. 如果需要保留其他文件，请注释掉对应的删除行\n");
-        script.push_str(&format!("#   3. 添加执行权限: chmod +x {}\n", output_path.display()));
-        script.push_str(&format!("#   4. 执行脚本: ./{}\n", output_path.file_name().unwrap().to_string_lossy()));
-        script.push_str("# ============================================================================\n\n");
-
-        // 安全检查
-        script.push_str("set -e  # 遇到错误立即退出\n");
-        script.push_str("set -u  # 使用未定义变量时报错\n\n");
-
-        // 交互式确认
-        script.push_str("# 确认提示\n");
-        script.push_str("echo \"⚠️  警告: 即将删除重复文件！\"\n");
-        script.push_str(&format!("echo \"扫描路径: {}\"\n", self.base_path.display()));
-        script.push_str(&format!("echo \"重复组数: {}\"\n", groups.len()));
-        
-        let deletable: usize = groups.iter().map(|g| g.len() - 1).sum();
-        let space_savings: u64 = groups
-            .iter()
-            .map(|g| g[0].size * (g.len() as u64 - 1))
-            .sum();
-        
-        script.push_str(&format!("echo \"将删除文件数: {}\"\n", deletable));
-        script.push_str(&format!("echo \"可节省空间: {}\"\n", format_size(space_savings)));
-        script.push_str("echo \"\"\n");
-        script.push_str("read -p \"确认要继续吗? (yes/no): \" confirm\n");
-        script.push_str("if [ \"$confirm\" != \"yes\" ]; then\n");
-        script.push_str("    echo \"❌ 已取消删除操作\"\n");
-        script.push_str("    exit 0\n");
-        script.push_str("fi\n\n");
-
-        // 统计变量
-        script.push_str("# 统计变量\n");
-        script.push_str("deleted_count=0\n");
-        script.push_str("deleted_size=0\n");
-        script.push_str("failed_count=0\n\n");
-
-        // 为每组生成删除命令
-        for (i, group) in groups.iter().enumerate() {
-            script.push_str(&format!("\n# ============================================================================\n"));
-            script.push_str(&format!("# 组 {}: {} 个重复文件 (大小: {} bytes)\n", 
-                i + 1, group.len(), group[0].size));
-            script.push_str(&format!("# ============================================================================\n"));
-            
-            // 显示保留的文件
-            let keep_path = if let Ok(abs) = group[0].path.canonicalize() {
-                abs.display().to_string()
-            } else {
-                group[0].path.display().to_string()
-            };
-            script.push_str(&format!("# 保留: {}\n", keep_path));
-            
-            // 删除其他文件
-            for (j, file) in group.iter().skip(1).enumerate() {
-                let file_path = if let Ok(abs) = file.path.canonicalize() {
-                    abs.display().to_string()
-                } else {
-                    file.path.display().to_string()
-                };
-                
-                script.push_str(&format!("\n# 删除文件 {}/{}\n", j + 1, group.len() - 1));
-                script.push_str(&format!("if [ -f \"{}\" ]; then\n", file_path));
-                script.push_str(&format!("    echo \"删除: {}\"\n", file_path));
-                script.push_str(&format!("    if rm \"{}\"; then\n", file_path));
-                script.push_str(&format!("        deleted_count=$((deleted_count + 1))\n"));
-                script.push_str(&format!("        deleted_size=$((deleted_size + {}))\n", file.size));
-                script.push_str("    else\n");
-                script.push_str(&format!("        echo \"❌ 删除失败: {}\"\n", file_path));
-                script.push_str("        failed_count=$((failed_count + 1))\n");
-                script.push_str("    fi\n");
-                script.push_str("else\n");
-                script.push_str(&format!("    echo \"⚠️  文件不存在: {}\"\n", file_path));
-                script.push_str("fi\n");
+    /// 遍历一个扫描根目录，返回其中每一项的路径和类型（不跟随符号链接）。
+    /// 被排除的目录直接剪枝，不会进入其中（扫描根目录本身不参与匹配）；
+    /// 指定 --respect-ignore 时使用 ignore 遍历器，同时遵守 .gitignore、.ignore 和 .dupfinderignore，
+    /// 并跳过 .git（git 不会忽略自己的目录，其中的对象文件不应参与去重）
+    fn walk_root<'a>(
+        &'a self,
+        root: &'a ScanRoot,
+        recursive: bool,
+    ) -> Box<dyn Iterator<Item = (PathBuf, fs::FileType)> + 'a> {
+        if self.respect_ignore {
+            let mut builder = WalkBuilder::new(&root.path);
+            builder
+                .standard_filters(false)
+                .git_ignore(true)
+                .git_global(true)
+                .git_exclude(true)
+                .ignore(true)
+                .parents(true)
+                // 不在 git 仓库中时同样遵守 .gitignore（例如整个家目录）
+                .require_git(false)
+                .add_custom_ignore_filename(IGNORE_FILE_NAME)
+                .follow_links(false);
+            if !recursive {
+                builder.max_depth(Some(1));
             }
+            // filter_entry 要求 'static，复制一份过滤条件
+            let filter = self.filter.clone();
+            let root_path = root.path.clone();
+            builder.filter_entry(move |entry| {
+                let path = entry.path();
+                entry.depth() == 0
+                    || (entry.file_name() != ".git"
+                        && !filter.prunes(
+                            path,
+                            path.strip_prefix(&root_path).unwrap_or(path),
+                            entry.file_type().is_some_and(|t| t.is_dir()),
+                        ))
+            });
+
+            return Box::new(builder.build().filter_map(|e| e.ok()).filter_map(|entry| {
+                let file_type = entry.file_type()?;
+                Some((entry.into_path(), file_type))
+            }));
         }
 
-        // 脚本结尾 - 显示统计信息
-        script.push_str("\n# ============================================================================\n");
-        script.push_str("# 删除完成，显示统计信息\n");
-        script.push_str("# ============================================================================\n");
-        script.push_str("echo \"\"\n");
-        script.push_str("echo \"==============================================================================\"\n");
-        script.push_str("echo \"📊 删除统计:\"\n");
-        script.push_str("echo \"==============================================================================\"\n");
-        script.push_str("echo \"✅ 成功删除: $deleted_count 个文件\"\n");
-        script.push_str("echo \"❌ 失败数量: $failed_count 个文件\"\n");
-        script.push_str("echo \"💾 节省空间: $(numfmt --to=iec-i --suffix=B $deleted_size 2>/dev/null || echo \\\"$deleted_size bytes\\\")\"\n");
-        script.push_str("echo \"==============================================================================\"\n");
-
-        Ok(script)
-    }
-
-    // ========================================================================
-    // 生成 PowerShell 脚本（Windows）
-    // ========================================================================
-    fn generate_powershell_script(&self, groups: &[Vec<FileInfo>], output_path: &Path) -> io::Result<String> {
-        let mut script = String::new();
-
-        let deletable: usize = groups.iter().map(|g| g.len() - 1).sum();
-        let space_savings: u64 = groups
-            .iter()
-            .map(|g| g[0].size * (g.len() as u64 - 1))
-            .sum();
-
-        // 脚本头部
-        script.push_str("# ============================================================================\n");
-        script.push_str("# DupFinder 自动生成的删除脚本 (PowerShell)\n");
-        script.push_str(&format!("# 生成时间: {}\n", Local::now().format("%Y-%m-%d %H:%M:%S")));
-        script.push_str(&format!("# 扫描路径: {}\n", self.base_path.display()));
-        script.push_str(&format!("# 重复组数: {}\n", groups.len()));
-        script.push_str("# ============================================================================\n");
-        script.push_str("#\n");
-        script.push_str("# ⚠️  警告：此脚本将删除重复文件！\n");
-        script.push_str("#    每组重复文件会保留第一个，删除其他的。\n");
-        script.push_str("#    请仔细检查后再执行！\n");
-        script.push_str("#\n");
-        script.push_str("# 使用方法:\n");
-        script.push_str("#   1. 仔细检查下面的删除命令\n");
-        script.push_str("#   2. 如果需要保留其他文件，请注释掉对应的删除行\n");
-        script.push_str(&format!("#   3. 执行脚本: PowerShell -ExecutionPolicy Bypass -File {}\n", output_path.file_name().unwrap().to_string_lossy()));
-        script.push_str("#   4. 或右键 -> 使用 PowerShell 运行\n");
-        script.push_str("# ============================================================================\n\n");
-
-        // 安全检查
-        script.push_str("# 设置错误处理\n");
-        script.push_str("$ErrorActionPreference = \"Stop\"\n\n");
-
-        // 交互式确认
-        script.push_str("# 确认提示\n");
-        script.push_str("Write-Host \"⚠️  警告: 即将删除重复文件！\" -ForegroundColor Yellow\n");
-        script.push_str(&format!("Write-Host \"扫描路径: {}\"\n", self.base_path.display()));
-        script.push_str(&format!("Write-Host \"重复组数: {}\"\n", groups.len()));
-        script.push_str(&format!("Write-Host \"将删除文件数: {}\"\n", deletable));
-        script.push_str(&format!("Write-Host \"可节省空间: {}\"\n", format_size(space_savings)));
-        script.push_str("Write-Host \"\"\n");
-        script.push_str("$confirm = Read-Host \"确认要继续吗? (yes/no)\"\n");
-        script.push_str("if ($confirm -ne \"yes\") {\n");
-        script.push_str("    Write-Host \"❌ 已取消删除操作\" -ForegroundColor Red\n");
-        script.push_str("    exit 0\n");
-        script.push_str("}\n\n");
-
-        // 统计变量
-        script.push_str("# 统计变量\n");
-        script.push_str("$deletedCount = 0\n");
-        script.push_str("$deletedSize = 0\n");
-        script.push_str("$failedCount = 0\n\n");
-
-        // 为每组生成删除命令
-        for (i, group) in groups.iter().enumerate() {
-            script.push_str("\n# ============================================================================\n");
-            script.push_str(&format!("# 组 {}: {} 个重复文件 (大小: {} bytes)\n", 
-                i + 1, group.len(), group[0].size));
-            script.push_str("# ============================================================================\n");
-            
-            // 显示保留的文件
-            let keep_path = if let Ok(abs) = group[0].path.canonicalize() {
-                abs.display().to_string()
-            } else {
-                group[0].path.display().to_string()
-            };
-            script.push_str(&format!("# 保留: {}\n", keep_path));
-            
-            // 删除其他文件
-            for (j, file) in group.iter().skip(1).enumerate() {
-                let file_path = if let Ok(abs) = file.path.canonicalize() {
-                    abs.display().to_string()
-                } else {
-                    file.path.display().to_string()
-                };
-                
-                script.push_str(&format!("\n# 删除文件 {}/{}\n", j + 1, group.len() - 1));
-                script.push_str(&format!("if (Test-Path \"{}\") {{\n", file_path));
-                script.push_str(&format!("    Write-Host \"删除: {}\"\n", file_path));
-                script.push_str("    try {\n");
-                script.push_str(&format!("        Remove-Item \"{}\" -Force\n", file_path));
-                script.push_str("        $deletedCount++\n");
-                script.push_str(&format!("        $deletedSize += {}\n", file.size));
-                script.push_str("    } catch {\n");
-                script.push_str(&format!("        Write-Host \"❌ 删除失败: {}\" -ForegroundColor Red\n", file_path));
-                script.push_str("        $failedCount++\n");
-                script.push_str("    }\n");
-                script.push_str("} else {\n");
-                script.push_str(&format!("    Write-Host \"⚠️  文件不存在: {}\" -ForegroundColor Yellow\n", file_path));
-                script.push_str("}\n");
-            }
+        let mut walk = WalkDir::new(&root.path);
+        if !recursive {
+            walk = walk.max_depth(1);
         }
-
-        // 脚本结尾 - 显示统计信息
-        script.push_str("\n# ============================================================================\n");
-        script.push_str("# 删除完成，显示统计信息\n");
-        script.push_str("# ============================================================================\n");
-        script.push_str("Write-Host \"\"\n");
-        script.push_str("Write-Host \"==============================================================================\" -ForegroundColor Cyan\n");
-        script.push_str("Write-Host \"📊 删除统计:\" -ForegroundColor Cyan\n");
-        script.push_str("Write-Host \"==============================================================================\" -ForegroundColor Cyan\n");
-        script.push_str("Write-Host \"✅ 成功删除: $deletedCount 个文件\" -ForegroundColor Green\n");
-        script.push_str("Write-Host \"❌ 失败数量: $failedCount 个文件\" -ForegroundColor Red\n");
-        script.push_str("$sizeInMB = [math]::Round($deletedSize / 1MB, 2)\n");
-        script.push_str("if ($sizeInMB -gt 0) {\n");
-        script.push_str("    Write-Host \"💾 节省空间: $sizeInMB MB ($deletedSize bytes)\" -ForegroundColor Green\n");
-        script.push_str("} else {\n");
-        script.push_str("    Write-Host \"💾 节省空间: $deletedSize bytes\" -ForegroundColor Green\n");
-        script.push_str("}\n");
-        script.push_str("Write-Host \"==============================================================================\" -ForegroundColor Cyan\n");
-        script.push_str("\n# 暂停，等待用户按键\n");
-        script.push_str("Write-Host \"\"\n");
-        script.push_str("Write-Host \"按任意键退出...\" -ForegroundColor Gray\n");
-        script.push_str("$null = $Host.UI.RawUI.ReadKey(\"NoEcho,IncludeKeyDown\")\n");
-
-        Ok(script)
+        let walker = walk.into_iter().filter_entry(move |entry| {
+            let path = entry.path();
+            entry.depth() == 0
+                || !self.filter.prunes(path, path.strip_prefix(&root.path).unwrap_or(path), entry.file_type().is_dir())
+        });
+        Box::new(walker.filter_map(|e| e.ok()).map(|entry| {
+            let file_type = entry.file_type();
+            (entry.into_path(), file_type)
+        }))
     }
 
     // ========================================================================
     // 主查找流程
     // ========================================================================
-    fn find_duplicates(&self, root: &Path, recursive: bool) -> Vec<Vec<FileInfo>> {
-        // 收集所有文件路径
-        let mut paths = Vec::new();
-        
-        let walker = if recursive {
-            WalkDir::new(root).into_iter()
-        } else {
-            WalkDir::new(root).max_depth(1).into_iter()
-        };
-
-        for entry in walker.filter_map(|e| e.ok()) {
-            if entry.file_type().is_file() {
-                let path = entry.path();
-                // 应用文件名过滤
-                if self.should_include_file(path) {
-                    paths.push(path.to_path_buf());
+    fn find_duplicates(&self, recursive: bool) -> Vec<Vec<FileInfo>> {
+        // 收集所有扫描目录下的文件
+        let mut files = Vec::new();
+        let mut skipped_symlinks = 0;
+
+        for (root_index, root) in self.roots.iter().enumerate() {
+            for (path, file_type) in self.walk_root(root, recursive) {
+                if file_type.is_file() {
+                    // 应用文件名（或相对路径）过滤
+                    let relative = path.strip_prefix(&root.path).unwrap_or(&path);
+                    if !self.filter.includes(&path, relative) {
+                        continue;
+                    }
+                    if let Ok(metadata) = fs::symlink_metadata(&path) {
+                        // 应用大小过滤（只读取元数据，不打开文件）
+                        if !self.filter.size_matches(metadata.len()) {
+                            continue;
+                        }
+                        // 应用修改时间过滤
+                        if !self.filter.time_matches(metadata.modified().ok()) {
+                            continue;
+                        }
+                        let is_reference = self.references.iter().any(|r| path.starts_with(r));
+                        files.push(FileInfo::new(path, &metadata, root_index, is_reference));
+                    }
+                } else if file_type.is_symlink() {
+                    // 不跟随符号链接，避免重复计算（包括之前用 --link symbolic 创建的链接）
+                    skipped_symlinks += 1;
+                    if self.verbose {
+                        let link_target = fs::read_link(&path)
+                            .map(|t| t.display().to_string())
+                            .unwrap_or_else(|_| "?".to_string());
+                        println!(
+                            "  {} 跳过符号链接: {} -> {}",
+                            "↪".dimmed(),
+                            path.display(),
+                            link_target
+                        );
+                    }
                 }
             }
         }
 
-        if paths.is_empty() {
-            if self.glob_set.is_some() || self.regex.is_some() {
+        if skipped_symlinks > 0 {
+            println!(
+                "{}",
+                format!("🔗 跳过了 {} 个符号链接", skipped_symlinks).dimmed()
+            );
+        }
+
+        // 固定文件顺序（先按扫描目录顺序，再按路径），保证多次运行的输出一致
+        files.sort_by(|a, b| a.root.cmp(&b.root).then_with(|| a.path.cmp(&b.path)));
+
+        if files.is_empty() {
+            if self.filter.has_includes() || self.filter.has_excludes() {
                 println!("{}", "⚠️  未找到匹配的文件".yellow());
             }
             return Vec::new();
@@ -894,22 +1176,108 @@ impl DupFinder {
 
         println!(
             "{}",
-            format!("🔎 开始扫描 {} 个文件...\n", paths.len()).green()
+            format!("🔎 开始扫描 {} 个文件...\n", files.len()).green()
         );
 
         // 执行 4 层验证
-        let size_groups = self.group_by_size(paths);
+        let size_groups = self.group_by_size(files);
         let partial_groups = self.group_by_partial_hash(size_groups);
         let full_groups = self.group_by_full_hash(partial_groups);
         let duplicates = self.verify_duplicates(full_groups);
+        self.arrange_groups(duplicates)
+    }
+
+    /// 整理最终结果：每组的第一个文件是保留的副本。
+    /// 排序规则依次为：参考目录中的文件、--keep-path-priority 中越靠前的模式、--keep 策略；
+    /// 只包含参考文件的组不需要处理，直接隐藏
+    fn arrange_groups(&self, groups: Vec<Vec<FileInfo>>) -> Vec<Vec<FileInfo>> {
+        groups
+            .into_iter()
+            .filter(|group| group.iter().any(|f| !f.is_reference))
+            .map(|mut group| {
+                // sort_by 是稳定排序，条件相同时保持扫描顺序
+                group.sort_by(|a, b| {
+                    (!a.is_reference)
+                        .cmp(&!b.is_reference)
+                        .then_with(|| self.keep_priority_rank(a).cmp(&self.keep_priority_rank(b)))
+                        .then_with(|| self.keep_policy.compare(a, b))
+                });
+                group
+            })
+            .collect()
+    }
 
-        duplicates
+    /// 文件匹配的第一个 --keep-path-priority 模式的序号，不匹配时排在最后
+    fn keep_priority_rank(&self, file: &FileInfo) -> usize {
+        self.keep_priority
+            .as_ref()
+            .and_then(|set| set.matches(&file.path).into_iter().min())
+            .unwrap_or(usize::MAX)
     }
 }
 
 // ============================================================================
 // 辅助函数
 // ============================================================================
+
+/// 逐字节比较两个文件的内容
+fn byte_compare(path1: &Path, path2: &Path) -> io::Result<bool> {
+    let mut file1 = File::open(path1)?;
+    let mut file2 = File::open(path2)?;
+
+    let mut buffer1 = vec![0u8; 8192];
+    let mut buffer2 = vec![0u8; 8192];
+
+    loop {
+        let bytes1 = read_full(&mut file1, &mut buffer1)?;
+        let bytes2 = read_full(&mut file2, &mut buffer2)?;
+
+        if bytes1 != bytes2 {
+            return Ok(false);
+        }
+
+        if bytes1 == 0 {
+            return Ok(true);
+        }
+
+        if buffer1[..bytes1] != buffer2[..bytes2] {
+            return Ok(false);
+        }
+    }
+}
+
+/// 尽量填满缓冲区，直到文件结束（单次 read 可能返回较少的字节）
+fn read_full(file: &mut File, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match file.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// 组内可以删除的文件：第一个文件是保留的副本，参考目录中的文件也始终保留
+fn removable_files(group: &[FileInfo]) -> impl Iterator<Item = &FileInfo> {
+    group.iter().skip(1).filter(|f| !f.is_reference)
+}
+
+/// 所有组中可删除的文件数
+fn deletable_count(groups: &[Vec<FileInfo>]) -> usize {
+    groups.iter().map(|g| removable_files(g).count()).sum()
+}
+
+/// 删除所有可删除文件后能节省的空间
+fn reclaimable_size(groups: &[Vec<FileInfo>]) -> u64 {
+    groups
+        .iter()
+        .flat_map(|g| removable_files(g))
+        .map(|f| f.size)
+        .sum()
+}
 fn format_size(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = KB * 1024;
@@ -926,63 +1294,101 @@ fn format_size(bytes: u64) -> String {
     }
 }
 
-// ============================================================================
-// Main 函数
-// ============================================================================
-fn main() {
-    let args = Args::parse();
+/// 命令行中选择的内置操作
+fn selected_action(args: &ScanArgs) -> Option<Action> {
+    if args.delete {
+        Some(Action::Delete)
+    } else if let Some(kind) = args.link {
+        Some(Action::Link {
+            kind,
+            relative: args.relative_symlinks,
+        })
+    } else if args.trash {
+        Some(Action::Trash)
+    } else {
+        args.move_to.as_ref().map(|dir| {
+            // 清单中记录绝对路径，以便在任意目录下恢复
+            Action::MoveTo(std::path::absolute(dir).unwrap_or_else(|_| dir.clone()))
+        })
+    }
+}
 
-    println!(
-        "{}",
-        "🔍 DupFinder - 重复文件查找工具".bright_cyan().bold()
-    );
-    
-    // 构建 GlobSet
-    let glob_set = if !args.patterns.is_empty() {
-        let mut builder = GlobSetBuilder::new();
-        for pattern in &args.patterns {
-            match Glob::new(pattern) {
-                Ok(glob) => {
-                    builder.add(glob);
-                }
-                Err(e) => {
-                    eprintln!("{} {}: {}", "❌ 无效的 glob 模式".red(), pattern, e);
-                    std::process::exit(1);
-                }
+/// 将多个 glob 模式编译为 GlobSet，模式无效时直接退出
+fn build_glob_set(patterns: &[String]) -> Option<GlobSet> {
+    if patterns.is_empty() {
+        return None;
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        match Glob::new(pattern) {
+            Ok(glob) => {
+                builder.add(glob);
             }
-        }
-        match builder.build() {
-            Ok(set) => Some(set),
             Err(e) => {
-                eprintln!("{} {}", "❌ 构建 glob 集合失败:".red(), e);
+                eprintln!("{} {}: {}", "❌ 无效的 glob 模式".red(), pattern, e);
                 std::process::exit(1);
             }
         }
-    } else {
-        None
-    };
-    
-    // 构建 Regex
-    let regex = if let Some(ref pattern) = args.regex_pattern {
-        match Regex::new(pattern) {
-            Ok(re) => Some(re),
-            Err(e) => {
-                eprintln!("{} {}: {}", "❌ 无效的正则表达式".red(), pattern, e);
-                std::process::exit(1);
-            }
+    }
+    match builder.build() {
+        Ok(set) => Some(set),
+        Err(e) => {
+            eprintln!("{} {}", "❌ 构建 glob 集合失败:".red(), e);
+            std::process::exit(1);
         }
-    } else {
-        None
-    };
-    
-    // 获取绝对路径作为基准路径
-    let base_path = args.path.canonicalize().unwrap_or_else(|_| args.path.clone());
-    
+    }
+}
+
+// ============================================================================
+// Main 函数
+// ============================================================================
+/// 按参数扫描重复文件，返回查找器和重复文件组（同时保存哈希缓存）
+fn scan(args: &ScanArgs) -> (DupFinder, Vec<Vec<FileInfo>>) {
     println!(
         "{}",
-        format!("📂 扫描路径: {}", args.path.display()).dimmed()
+        "🔍 DupFinder - 重复文件查找工具".bright_cyan().bold()
     );
     
+    // 构建保留优先级的 GlobSet（文件过滤条件由 WalkFilter 构建）
+    let keep_priority = build_glob_set(&args.keep_path_priority);
+
+    // 处理递归选项（默认递归，除非指定 --no-recursive）
+    let do_recursive = !args.no_recursive && args.recursive;
+
+    // 获取绝对路径作为扫描根目录，并去掉重叠的部分
+    let references = canonicalize_all(&args.references);
+    let roots = resolve_roots(&args.paths, &references, do_recursive);
+    if roots.is_empty() {
+        eprintln!("{}", "❌ 没有可扫描的路径".red());
+        std::process::exit(1);
+    }
+
+    if roots.len() == 1 {
+        println!(
+            "{}",
+            format!(
+                "📂 扫描路径: {}{}",
+                roots[0].path.display(),
+                if roots[0].reference { "（参考）" } else { "" }
+            )
+            .dimmed()
+        );
+    } else {
+        for root in &roots {
+            println!(
+                "{}",
+                format!(
+                    "📂 扫描路径 [{}]: {}{}",
+                    root.label,
+                    root.path.display(),
+                    if root.reference { "（参考）" } else { "" }
+                )
+                .dimmed()
+            );
+        }
+    }
+    
     // 显示过滤条件
     if !args.patterns.is_empty() {
         println!(
@@ -996,9 +1402,44 @@ fn main() {
             format!("🔍 正则表达式: {}", regex_pattern).dimmed()
         );
     }
-    
-    // 处理递归选项（默认递归，除非指定 --no-recursive）
-    let do_recursive = !args.no_recursive && args.recursive;
+    if args.match_path && (!args.patterns.is_empty() || args.regex_pattern.is_some()) {
+        println!("{}", "🔍 匹配方式: 相对路径".dimmed());
+    }
+    if args.filter_logic == FilterLogic::And && !args.patterns.is_empty() && args.regex_pattern.is_some() {
+        println!("{}", "🔍 组合方式: glob 与正则表达式都必须匹配".dimmed());
+    }
+    if !args.excludes.is_empty() {
+        println!(
+            "{}",
+            format!("🚫 排除模式: {}", args.excludes.join(", ")).dimmed()
+        );
+    }
+    if !args.exclude_regexes.is_empty() {
+        println!(
+            "{}",
+            format!("🚫 排除正则: {}", args.exclude_regexes.join(", ")).dimmed()
+        );
+    }
+    if args.min_size.is_some() || args.max_size.is_some() {
+        let min = args.min_size.map(format_size).unwrap_or_else(|| "0 B".to_string());
+        let max = args.max_size.map(format_size).unwrap_or_else(|| "不限".to_string());
+        println!("{}", format!("📏 文件大小: {} - {}", min, max).dimmed());
+    }
+    if args.include_empty {
+        println!("{}", "📄 包含空文件".dimmed());
+    }
+    if let Some(time) = args.older_than {
+        println!("{}", format!("🕒 修改时间早于: {}", filter::format_time(time)).dimmed());
+    }
+    if let Some(time) = args.newer_than {
+        println!("{}", format!("🕒 修改时间不早于: {}", filter::format_time(time)).dimmed());
+    }
+    if args.respect_ignore {
+        println!(
+            "{}",
+            format!("🚫 遵守忽略规则: .gitignore, .ignore, {}", IGNORE_FILE_NAME).dimmed()
+        );
+    }
     
     if do_recursive {
         println!("{}", "🔄 递归模式: 开启".dimmed());
@@ -1010,37 +1451,223 @@ fn main() {
         println!("{}", "📍 路径显示: 相对路径".dimmed());
     }
     
+    // 加载哈希缓存（失败时给出警告并继续，不影响扫描）
+    let cache = if args.no_cache {
+        None
+    } else {
+        args.cache.clone().or_else(HashCache::default_path).and_then(|cache_path| {
+            match HashCache::load(&cache_path, args.hash_algorithm) {
+                Ok(cache) => Some(cache),
+                Err(e) => {
+                    eprintln!(
+                        "{} {}: {}",
+                        "⚠️  无法读取哈希缓存".yellow(),
+                        cache_path.display(),
+                        e
+                    );
+                    None
+                }
+            }
+        })
+    };
+
+    let filter = match WalkFilter::new(args) {
+        Ok(filter) => filter,
+        Err(e) => {
+            eprintln!("{} {}", "❌".red(), e);
+            std::process::exit(1);
+        }
+    };
+
+    let finder = DupFinder::new(args, roots, references, filter, keep_priority, cache);
+
     if args.verbose {
         println!("{}", "📋 详细模式: 开启".dimmed());
+        println!("{}", format!("🧵 哈希线程数: {}", finder.threads).dimmed());
+        println!("{}", format!("🔐 哈希算法: {}", finder.hash_algorithm.name()).dimmed());
+        if let Some(ref cache) = finder.cache {
+            println!("{}", format!("🗄️  哈希缓存: {}", cache.path().display()).dimmed());
+        }
     }
     
     println!();
 
-    let finder = DupFinder::new(
-        args.verbose,
-        args.size,
-        args.hardlinks,
-        args.relative_path,
-        base_path.clone(),
-        glob_set,
-        regex,
-    );
-    let duplicates = finder.find_duplicates(&args.path, do_recursive);
-    finder.display_results(&duplicates);
+    let duplicates = finder.find_duplicates(do_recursive);
+
+    // 保存哈希缓存
+    if let Some(ref cache) = finder.cache {
+        if args.cache_prune {
+            let removed = cache.prune();
+            println!("{}", format!("🧹 已清理 {} 条失效的缓存记录", removed).dimmed());
+        }
+        if args.verbose {
+            let (hits, misses) = cache.stats();
+            println!(
+                "{}",
+                format!("🗄️  缓存命中 {} 次，未命中 {} 次", hits, misses).dimmed()
+            );
+        }
+        if let Err(e) = cache.save() {
+            eprintln!("{} {}", "⚠️  哈希缓存保存失败:".yellow(), e);
+        }
+    }
+
+    (finder, duplicates)
+}
+
+/// 打开操作日志，未指定位置时使用默认位置
+fn open_journal(journal_path: Option<&Path>, hash_algorithm: HashAlgorithm) -> Result<Journal, String> {
+    let path = journal_path
+        .map(Path::to_path_buf)
+        .or_else(Journal::default_path)
+        .ok_or_else(|| "无法确定操作日志位置，请使用 --journal 指定".to_string())?;
+    Journal::open(&path, hash_algorithm).map_err(|e| format!("无法创建操作日志 {}: {}", path.display(), e))
+}
+
+/// 执行处理计划并输出统计（演练模式不修改文件，无需记录操作日志）；
+/// `hash_algorithm` 为计划中文件哈希使用的算法。
+/// 无法创建操作日志时不修改任何文件，日志写入失败时停止处理，两种情况都以非零状态退出
+fn apply_plans(
+    plans: &[GroupPlan],
+    action: &Action,
+    dry_run: bool,
+    journal_path: Option<&Path>,
+    hash_algorithm: HashAlgorithm,
+) {
+    let mut journal = if dry_run {
+        None
+    } else {
+        match open_journal(journal_path, hash_algorithm) {
+            Ok(journal) => Some(journal),
+            Err(e) => {
+                eprintln!("{} {}", "❌ 未处理任何文件:".red(), e);
+                std::process::exit(1);
+            }
+        }
+    };
+    let summary = actions::execute(plans, action, dry_run, journal.as_mut());
+    actions::display_summary(&summary, action, dry_run);
+    if summary.aborted {
+        std::process::exit(1);
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+
+    if let Some(command) = args.command {
+        match command {
+            Command::Restore { manifest } => match quarantine::restore(&manifest) {
+                Ok(0) => {}
+                // 部分文件未能恢复，详情已在输出中列出
+                Ok(_) => std::process::exit(1),
+                Err(e) => {
+                    eprintln!("{} {}", "❌ 恢复失败:".red(), e);
+                    std::process::exit(1);
+                }
+            },
+            Command::Undo { journal } => match journal::undo(&journal) {
+                Ok(0) => {}
+                // 部分操作未能撤销，详情已在输出中列出
+                Ok(_) => std::process::exit(1),
+                Err(e) => {
+                    eprintln!("{} {}", "❌ 撤销失败:".red(), e);
+                    std::process::exit(1);
+                }
+            },
+            Command::Apply {
+                report: report_path,
+                action,
+                link_kind,
+                relative_symlinks,
+                move_to,
+                dry_run,
+                journal,
+            } => {
+                let action = match (action, move_to) {
+                    (ReportAction::Delete, _) => Action::Delete,
+                    (ReportAction::Link, _) => Action::Link {
+                        kind: link_kind,
+                        relative: relative_symlinks,
+                    },
+                    (ReportAction::Move, Some(dir)) => {
+                        Action::MoveTo(std::path::absolute(&dir).unwrap_or(dir))
+                    }
+                    (ReportAction::Move, None) => unreachable!("clap 保证 --move-to 已指定"),
+                };
+                let report = match report::load(&report_path) {
+                    Ok(report) => report,
+                    Err(e) => {
+                        eprintln!("{} {}: {}", "❌ 无法读取报告".red(), report_path.display(), e);
+                        std::process::exit(1);
+                    }
+                };
+
+                let (plans, rejected) = report::plan_from_report(&report);
+                if rejected > 0 {
+                    println!(
+                        "{}",
+                        format!("⚠️  {} 个文件与报告不一致，已拒绝处理", rejected).yellow()
+                    );
+                }
+                if plans.is_empty() {
+                    println!("{}", "✅ 没有需要处理的文件".green());
+                } else {
+                    apply_plans(&plans, &action, dry_run, journal.as_deref(), report.scan_info.hash_algorithm);
+                }
+            }
+            Command::Tui(args) => {
+                let (finder, duplicates) = scan(&args);
+                if duplicates.is_empty() {
+                    println!("{}", "✅ 未发现重复文件".green());
+                    return;
+                }
+                match tui::run(&finder, &args, duplicates) {
+                    Ok(Some((plans, action))) => {
+                        apply_plans(&plans, &action, args.dry_run, args.journal.as_deref(), finder.hash_algorithm)
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        eprintln!("{} {}", "❌ 终端界面出错:".red(), e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+        return;
+    }
+
+    let args = args.scan;
+    let (finder, duplicates) = scan(&args);
+    let action = selected_action(&args);
+    finder.display_results(&duplicates, action.as_ref());
 
     // JSON 输出
-    if let Some(json_path) = args.json {
-        if let Err(e) = finder.export_json(&duplicates, &json_path) {
+    if let Some(ref json_path) = args.json {
+        if let Err(e) = finder.export_json(&duplicates, json_path) {
             eprintln!("{} {}", "❌ JSON 输出失败:".red(), e);
         }
     }
 
     // 生成删除脚本
-    if let Some(script_path) = args.delete_script {
-        if let Err(e) = finder.generate_delete_script(&duplicates, &script_path) {
+    if let Some(ref script_path) = args.delete_script {
+        let format = args.script_format.unwrap_or_else(ScriptFormat::host_default);
+        if let Err(e) = finder.generate_delete_script(&duplicates, script_path, format, &args.script_path_map) {
             eprintln!("{} {}", "❌ 删除脚本生成失败:".red(), e);
         }
     }
+
+    // 内置操作（在报告和脚本生成之后执行，报告反映的是扫描时的状态）
+    if let Some(action) = action {
+        if !duplicates.is_empty() {
+            let plans = if args.interactive {
+                interactive::select_plans(&finder, &duplicates)
+            } else {
+                actions::plan_groups(&duplicates)
+            };
+            apply_plans(&plans, &action, args.dry_run, args.journal.as_deref(), finder.hash_algorithm);
+        }
+    }
 }
 
 // ============================================================================
@@ -1056,7 +1683,7 @@ fn main() {
 //    - ? 操作符简化错误传播
 //
 // 3. 集合类型：
-//    - HashMap 用于分组
+//    - BTreeMap 用于分组（按键有序，输出稳定）
 //    - Vec 用于存储列表
 //
 // 4. 迭代器：
@@ -1071,3 +1698,216 @@ fn main() {
 //    - &self 借用，self 获取所有权
 //
 // ============================================================================
+
+/// 测试用的临时目录（每次调用都会清空重建）
+#[cfg(test)]
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dupfinder-test-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finder_for(dir: &Path, extra: &[&str]) -> DupFinder {
+        let mut argv = vec!["dupfinder", dir.to_str().unwrap(), "--no-cache"];
+        argv.extend_from_slice(extra);
+        let args = Args::parse_from(argv).scan;
+        let roots = resolve_roots(&args.paths, &[], true);
+        let filter = WalkFilter::new(&args).unwrap();
+        DupFinder::new(&args, roots, Vec::new(), filter, None, None)
+    }
+
+    /// 把所有文件放进同一个哈希组，模拟哈希完全碰撞
+    fn collide(dir: &Path, names: &[&str]) -> BTreeMap<String, Vec<FileInfo>> {
+        let files = names
+            .iter()
+            .map(|name| {
+                let path = dir.join(name);
+                let metadata = fs::metadata(&path).unwrap();
+                FileInfo::new(path, &metadata, 0, false)
+            })
+            .collect();
+        BTreeMap::from([("collision".to_string(), files)])
+    }
+
+    fn names(groups: &[Vec<FileInfo>]) -> Vec<Vec<String>> {
+        groups
+            .iter()
+            .map(|g| g.iter().map(|f| f.path.file_name().unwrap().to_string_lossy().into_owned()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn verify_duplicates_splits_colliding_hash_group_into_classes() {
+        let dir = test_dir("collision");
+        for (name, content) in [("a", "AAAA"), ("b1", "BBBB"), ("b2", "BBBB"), ("c1", "CCCC"), ("c2", "CCCC")] {
+            fs::write(dir.join(name), content).unwrap();
+        }
+
+        let finder = finder_for(&dir, &[]);
+        let groups = finder.verify_duplicates(collide(&dir, &["a", "b1", "b2", "c1", "c2"]));
+        assert_eq!(names(&groups), vec![vec!["b1", "b2"], vec!["c1", "c2"]]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn verify_duplicates_skips_hardlinks_of_class_members() {
+        let dir = test_dir("collision-hardlink");
+        for (name, content) in [("a", "AAAA"), ("b1", "BBBB"), ("b2", "BBBB"), ("c1", "CCCC")] {
+            fs::write(dir.join(name), content).unwrap();
+        }
+        fs::hard_link(dir.join("b1"), dir.join("b1-link")).unwrap();
+        fs::hard_link(dir.join("c1"), dir.join("c1-link")).unwrap();
+        let all = ["a", "b1", "b1-link", "b2", "c1", "c1-link"];
+
+        // 默认跳过硬链接：c1 只有自己的硬链接，不构成重复
+        let finder = finder_for(&dir, &[]);
+        let groups = finder.verify_duplicates(collide(&dir, &all));
+        assert_eq!(names(&groups), vec![vec!["b1", "b2"]]);
+
+        // -H 时硬链接也作为重复文件
+        let finder = finder_for(&dir, &["-H"]);
+        let groups = finder.verify_duplicates(collide(&dir, &all));
+        assert_eq!(names(&groups), vec![vec!["b1", "b1-link", "b2"], vec!["c1", "c1-link"]]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn later_scan_without_move_to_skips_quarantine_dir() {
+        let dir = test_dir("quarantine-later-scan");
+        let quarantine = dir.join("quarantine");
+        fs::write(dir.join("x1"), "XXXX").unwrap();
+        fs::write(dir.join("x2"), "XXXX").unwrap();
+
+        let finder = finder_for(&dir, &["--move-to", quarantine.to_str().unwrap()]);
+        let plans = actions::plan_groups(&finder.find_duplicates(true));
+        let summary = actions::execute(&plans, &Action::MoveTo(quarantine.clone()), false, None);
+        assert_eq!(summary.processed_files, 1);
+        assert!(quarantine.join(quarantine::MARKER_FILE_NAME).is_file());
+
+        // 之后不带 --move-to 的扫描：隔离的副本不应与原文件组成重复
+        let finder = finder_for(&dir, &[]);
+        assert!(finder.find_duplicates(true).is_empty());
+        let finder = finder_for(&dir, &["--respect-ignore"]);
+        assert!(finder.find_duplicates(true).is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_linking_counts_only_files_without_other_links() {
+        let dir = test_dir("hardlink-reclaimed");
+        fs::create_dir_all(dir.join("elsewhere")).unwrap();
+        for name in ["keep", "single", "shared"] {
+            fs::write(dir.join(name), "AAAA").unwrap();
+        }
+        // shared 在扫描范围之外还有一个硬链接，替换它不会释放空间
+        fs::hard_link(dir.join("shared"), dir.join("elsewhere/shared")).unwrap();
+        let file = |name: &str| {
+            let path = dir.join(name);
+            let metadata = fs::metadata(&path).unwrap();
+            FileInfo::new(path, &metadata, 0, false)
+        };
+        let plans = [GroupPlan { keep: file("keep"), targets: vec![file("single"), file("shared")] }];
+        let action = Action::Link { kind: LinkKind::Hard, relative: false };
+
+        let preview = actions::execute(&plans, &action, true, None);
+        assert_eq!(preview.reclaimed_bytes, 4);
+        let summary = actions::execute(&plans, &action, false, None);
+        assert_eq!(summary.processed_files, 2);
+        assert_eq!(summary.reclaimed_bytes, 4);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn open_journal_fails_instead_of_running_without_undo() {
+        let dir = test_dir("journal-open-fails");
+        fs::write(dir.join("file"), "").unwrap();
+        // 日志路径的父目录是普通文件，无法创建
+        assert!(open_journal(Some(&dir.join("file").join("journal.jsonl")), HashAlgorithm::Md5).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn execute_stops_when_journal_append_fails() {
+        let dir = test_dir("journal-append-fails");
+        for (name, content) in [("a1", "AAAA"), ("a2", "AAAA"), ("b1", "BBBB"), ("b2", "BBBB")] {
+            fs::write(dir.join(name), content).unwrap();
+        }
+        let plans = actions::plan_groups(&finder_for(&dir, &[]).find_duplicates(true));
+        assert_eq!(plans.len(), 2);
+
+        // 写入 /dev/full 总是失败（ENOSPC）
+        let mut journal = Journal::open(Path::new("/dev/full"), HashAlgorithm::Md5).unwrap();
+        let summary = actions::execute(&plans, &Action::Delete, false, Some(&mut journal));
+
+        assert!(summary.aborted);
+        assert_eq!(summary.processed_files, 1);
+        assert!(!dir.join("a2").exists());
+        assert!(dir.join("b2").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn exclude_with_trailing_slash_skips_only_directories() {
+        let dir = test_dir("exclude-dir-only");
+        fs::create_dir_all(dir.join("sub/build")).unwrap();
+        fs::write(dir.join("build"), "AAAA").unwrap();
+        fs::write(dir.join("a"), "AAAA").unwrap();
+        fs::write(dir.join("sub/build/b"), "AAAA").unwrap();
+
+        // 名为 build 的文件不受 build/ 影响，sub/build 目录被跳过
+        let finder = finder_for(&dir, &["--exclude", "build/"]);
+        assert_eq!(names(&finder.find_duplicates(true)), vec![vec!["a", "build"]]);
+        let finder = finder_for(&dir, &["--exclude", "build"]);
+        assert!(finder.find_duplicates(true).is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn respect_ignore_skips_git_dir() {
+        let dir = test_dir("respect-ignore-git");
+        fs::create_dir_all(dir.join(".git/objects")).unwrap();
+        fs::create_dir_all(dir.join("sub/.git")).unwrap();
+        fs::write(dir.join(".gitignore"), "*.log\n").unwrap();
+        fs::write(dir.join("a1"), "AAAA").unwrap();
+        fs::write(dir.join("a2"), "AAAA").unwrap();
+        fs::write(dir.join("b"), "BBBB").unwrap();
+        fs::write(dir.join("b.log"), "BBBB").unwrap();
+        fs::write(dir.join(".git/objects/b"), "BBBB").unwrap();
+        fs::write(dir.join("sub/.git/b"), "BBBB").unwrap();
+
+        let finder = finder_for(&dir, &["--respect-ignore"]);
+        assert_eq!(names(&finder.find_duplicates(true)), vec![vec!["a1", "a2"]]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn find_duplicates_skips_quarantine_dir_inside_root() {
+        let dir = test_dir("quarantine-in-root");
+        let quarantine = dir.join("quarantine");
+        fs::create_dir_all(quarantine.join("old")).unwrap();
+        fs::write(dir.join("a1"), "AAAA").unwrap();
+        fs::write(dir.join("a2"), "AAAA").unwrap();
+        fs::write(dir.join("b"), "BBBB").unwrap();
+        // 上一次隔离的文件与扫描目录中的 b 相同，不应再被当作重复文件
+        fs::write(quarantine.join("old").join("b"), "BBBB").unwrap();
+
+        let finder = finder_for(&dir, &["--move-to", quarantine.to_str().unwrap()]);
+        assert_eq!(names(&finder.find_duplicates(true)), vec![vec!["a1", "a2"]]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,238 @@
+// ============================================================================
+// 隔离模式：把重复文件移动到隔离目录，并记录恢复清单
+// 隔离目录中保留原始的目录结构，`dupfinder restore 清单文件` 可以全部放回原处
+// ============================================================================
+
+use chrono::Local;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, FileTimes, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// 隔离目录的标记文件：遍历时跳过含有该文件的目录，
+/// 之后不带 --move-to 的扫描也不会把隔离的副本当作重复文件
+pub const MARKER_FILE_NAME: &str = ".dupfinder-quarantine";
+
+/// 清单中的一条记录（JSON Lines 格式，每移动一个文件追加一行）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub original_path: PathBuf,
+    pub quarantine_path: PathBuf,
+    pub size: u64,
+    pub hash: Option<String>,
+    pub kept_path: PathBuf,
+    pub timestamp: String,
+}
+
+/// 恢复清单写入器
+pub struct Manifest {
+    path: PathBuf,
+    file: File,
+}
+
+impl Manifest {
+    /// 在隔离目录中创建新的清单文件
+    pub fn create(quarantine_dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(quarantine_dir)?;
+        let marker = quarantine_dir.join(MARKER_FILE_NAME);
+        if !marker.is_file() {
+            fs::write(marker, "dupfinder 隔离目录，扫描时会跳过此目录\n")?;
+        }
+        let name = format!("manifest-{}.jsonl", Local::now().format("%Y%m%d-%H%M%S"));
+        let path = quarantine_dir.join(name);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Manifest { path, file })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 追加一条记录并立即写入磁盘，程序中断时已移动的文件仍可恢复
+    pub fn append(&mut self, entry: &ManifestEntry) -> io::Result<()> {
+        let line = serde_json::to_string(entry)?;
+        writeln!(self.file, "{}", line)?;
+        self.file.sync_data()
+    }
+}
+
+/// 目录是否为隔离目录（含有标记文件）
+pub fn is_quarantine_dir(dir: &Path) -> bool {
+    dir.join(MARKER_FILE_NAME).is_file()
+}
+
+/// 文件在隔离目录中的位置：隔离目录 + 原始绝对路径
+/// 例如 /data/a.txt -> <隔离目录>/data/a.txt，C:\data\a.txt -> <隔离目录>\C\data\a.txt
+pub fn quarantine_path(quarantine_dir: &Path, original: &Path) -> PathBuf {
+    let mut result = quarantine_dir.to_path_buf();
+    for component in original.components() {
+        match component {
+            Component::Prefix(prefix) => {
+                let drive: String = prefix
+                    .as_os_str()
+                    .to_string_lossy()
+                    .chars()
+                    .filter(|c| c.is_alphanumeric())
+                    .collect();
+                result.push(drive);
+            }
+            Component::RootDir | Component::CurDir => {}
+            Component::ParentDir => result.push("_"),
+            Component::Normal(name) => result.push(name),
+        }
+    }
+    result
+}
+
+/// 移动文件（跨文件系统时复制后删除），不会覆盖已存在的文件
+pub fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if to.symlink_metadata().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("目标位置已存在文件: {}", to.display()),
+        ));
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    // rename 无法跨文件系统，改为复制后删除
+    copy_then_remove(from, to)
+}
+
+/// 复制文件并保留权限、访问时间和修改时间（恢复后哈希缓存仍然有效），成功后删除源文件
+fn copy_then_remove(from: &Path, to: &Path) -> io::Result<()> {
+    let metadata = fs::metadata(from)?;
+    let result = fs::copy(from, to).and_then(|_| {
+        let mut times = FileTimes::new().set_modified(metadata.modified()?);
+        if let Ok(accessed) = metadata.accessed() {
+            times = times.set_accessed(accessed);
+        }
+        open_for_times(to)?.set_times(times)?;
+        // fs::copy 已复制权限位，这里再设置一次以防目标文件系统的默认权限不同
+        fs::set_permissions(to, metadata.permissions())?;
+        fs::remove_file(from)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(to);
+    }
+    result
+}
+
+/// 打开文件以修改时间戳：Unix 上文件所有者只读打开即可（复制出的文件可能继承只读权限），
+/// 其他平台需要写权限
+#[cfg(unix)]
+pub fn open_for_times(path: &Path) -> io::Result<File> {
+    File::open(path)
+}
+
+#[cfg(not(unix))]
+pub fn open_for_times(path: &Path) -> io::Result<File> {
+    File::options().write(true).open(path)
+}
+
+/// 读取清单文件
+pub fn read_manifest(path: &Path) -> io::Result<Vec<ManifestEntry>> {
+    let mut entries = Vec::new();
+    for (i, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("第 {} 行格式错误: {}", i + 1, e))
+        })?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// 根据清单把隔离的文件放回原位置，返回跳过或恢复失败的文件数
+pub fn restore(manifest_path: &Path) -> io::Result<usize> {
+    let entries = read_manifest(manifest_path)?;
+
+    println!(
+        "{}",
+        format!("📦 恢复清单: {}（{} 个文件）", manifest_path.display(), entries.len()).dimmed()
+    );
+
+    let mut restored = 0;
+    let mut failed = 0;
+
+    for entry in &entries {
+        if entry.original_path.symlink_metadata().is_ok() {
+            println!(
+                "  {} 跳过 {}: 原位置已存在文件",
+                "✗".red(),
+                entry.original_path.display()
+            );
+            failed += 1;
+            continue;
+        }
+
+        match move_file(&entry.quarantine_path, &entry.original_path) {
+            Ok(()) => {
+                println!("  {} 已恢复 {}", "✓".green(), entry.original_path.display());
+                restored += 1;
+            }
+            Err(e) => {
+                println!("  {} 恢复失败 {}: {}", "✗".red(), entry.original_path.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("\n{}", "=".repeat(70));
+    println!("{}", "📈 恢复结果:".cyan().bold());
+    println!("  已恢复文件数: {}", restored);
+    if failed > 0 {
+        println!("  {}", format!("跳过/失败: {} 个文件", failed).red());
+    }
+    println!("{}", "=".repeat(70));
+
+    Ok(failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn copy_fallback_keeps_times_and_permissions() {
+        let dir = crate::test_dir("quarantine-copy");
+        let (from, to) = (dir.join("from"), dir.join("to"));
+        fs::write(&from, "data").unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let accessed = SystemTime::UNIX_EPOCH + Duration::from_secs(1_650_000_000);
+        File::options()
+            .write(true)
+            .open(&from)
+            .unwrap()
+            .set_times(FileTimes::new().set_modified(modified).set_accessed(accessed))
+            .unwrap();
+        let mut permissions = fs::metadata(&from).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&from, permissions).unwrap();
+
+        copy_then_remove(&from, &to).unwrap();
+
+        assert!(!from.exists());
+        let metadata = fs::metadata(&to).unwrap();
+        assert_eq!(fs::read_to_string(&to).unwrap(), "data");
+        assert_eq!(metadata.modified().unwrap(), modified);
+        assert_eq!(metadata.accessed().unwrap(), accessed);
+        assert!(metadata.permissions().readonly());
+
+        let mut permissions = metadata.permissions();
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(&to, permissions).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
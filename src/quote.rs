@@ -0,0 +1,271 @@
+// ============================================================================
+// 脚本转义：生成的脚本必须把任意文件名当作纯文本，不能被 shell 解释执行
+// 文件名中可能出现引号、$、反引号、换行，Unix 上还可能不是合法的 UTF-8
+// ============================================================================
+
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+
+/// 文件名的原始字节
+#[cfg(unix)]
+pub fn os_bytes(s: &OsStr) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(s.as_bytes())
+}
+
+#[cfg(not(unix))]
+pub fn os_bytes(s: &OsStr) -> Cow<'_, [u8]> {
+    Cow::Owned(s.to_string_lossy().into_owned().into_bytes())
+}
+
+/// os_bytes 的逆操作
+#[cfg(unix)]
+pub fn os_from_bytes(bytes: Vec<u8>) -> OsString {
+    use std::os::unix::ffi::OsStringExt;
+    OsString::from_vec(bytes)
+}
+
+#[cfg(not(unix))]
+pub fn os_from_bytes(bytes: Vec<u8>) -> OsString {
+    OsString::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// POSIX 单引号字符串：引号内没有任何特殊字符，单引号本身写成 '\''
+pub fn single_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// bash 中的一个单词：普通文件名使用单引号；
+/// 含控制字符或非 UTF-8 字节时使用 $'...'，这些字节写成 \xNN
+pub fn bash_quote(s: &OsStr) -> String {
+    let bytes = os_bytes(s);
+    match std::str::from_utf8(&bytes) {
+        Ok(text) if !text.chars().any(char::is_control) => single_quote(text),
+        _ => ansi_c_quote(&bytes),
+    }
+}
+
+fn push_hex_bytes(out: &mut String, bytes: &[u8]) {
+    for byte in bytes {
+        out.push_str(&format!("\\x{:02x}", byte));
+    }
+}
+
+/// bash 的 $'...' 字符串
+fn ansi_c_quote(bytes: &[u8]) -> String {
+    let mut out = String::from("$'");
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\\' => out.push_str(r"\\"),
+                '\'' => out.push_str(r"\'"),
+                c if c.is_control() => push_hex_bytes(&mut out, c.encode_utf8(&mut [0; 4]).as_bytes()),
+                c => out.push(c),
+            }
+        }
+        push_hex_bytes(&mut out, chunk.invalid());
+    }
+    out.push('\'');
+    out
+}
+
+/// POSIX sh 中的一个单词：没有 $'...'，单引号内可以直接包含换行；
+/// 其他控制字符和非 UTF-8 字节用 "$(printf '\NNN')" 生成
+/// （命令替换会去掉末尾的换行，所以换行始终放在单引号内）
+pub fn posix_quote(s: &OsStr) -> String {
+    // (是否需要 printf, 内容)：相邻的同类片段合并
+    let mut segments: Vec<(bool, String)> = Vec::new();
+    let mut push = |escaped: bool, text: &str| match segments.last_mut() {
+        Some((kind, content)) if *kind == escaped => content.push_str(text),
+        _ => segments.push((escaped, text.to_string())),
+    };
+
+    let bytes = os_bytes(s);
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            if c.is_control() && c != '\n' {
+                for byte in c.encode_utf8(&mut [0; 4]).as_bytes() {
+                    push(true, &format!("\\{:03o}", byte));
+                }
+            } else {
+                push(false, c.encode_utf8(&mut [0; 4]));
+            }
+        }
+        for byte in chunk.invalid() {
+            push(true, &format!("\\{:03o}", byte));
+        }
+    }
+
+    if segments.is_empty() {
+        return "''".to_string();
+    }
+    segments
+        .iter()
+        .map(|(escaped, content)| {
+            if *escaped {
+                format!("\"$(printf '{}')\"", content)
+            } else {
+                single_quote(content)
+            }
+        })
+        .collect()
+}
+
+/// cmd.exe 批处理中的双引号字符串：引号内 & | < > ^ 都是普通字符，% 写成 %%。
+/// 包含双引号、控制字符或非 UTF-8 字节的路径无法安全表示，返回 None
+pub fn cmd_quote(s: &OsStr) -> Option<String> {
+    let text = s.to_str()?;
+    if text.chars().any(|c| c == '"' || c.is_control()) {
+        return None;
+    }
+    Some(format!("\"{}\"", text.replace('%', "%%")))
+}
+
+/// Python 字符串字面量；非 UTF-8 的文件名使用 os.fsdecode(b"...")
+pub fn python_literal(s: &OsStr) -> String {
+    let bytes = os_bytes(s);
+    match std::str::from_utf8(&bytes) {
+        // JSON 字符串同时也是合法的 Python 字符串字面量
+        Ok(text) => serde_json::to_string(text).unwrap_or_default(),
+        Err(_) => {
+            let mut out = String::from("os.fsdecode(b\"");
+            for &byte in bytes.iter() {
+                match byte {
+                    b'"' | b'\\' => {
+                        out.push('\\');
+                        out.push(byte as char);
+                    }
+                    0x20..=0x7e => out.push(byte as char),
+                    _ => out.push_str(&format!("\\x{:02x}", byte)),
+                }
+            }
+            out.push_str("\")");
+            out
+        }
+    }
+}
+
+/// PowerShell 字面量字符串：单引号内不展开变量和表达式，只需把引号字符写两遍
+/// （PowerShell 把 ‘ ’ ‚ ‛ 也当作单引号）。PowerShell 字符串只能表示 Unicode，
+/// 非 UTF-8 的文件名会被有损转换，脚本执行时会报告文件不存在而不会误删
+pub fn powershell_quote(s: &OsStr) -> String {
+    let mut out = String::from("'");
+    for c in s.to_string_lossy().chars() {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}') {
+            out.push(c);
+        }
+        out.push(c);
+    }
+    out.push('\'');
+    out
+}
+
+/// 注释中的文本：控制字符（尤其是换行）写成转义形式，避免后面的内容逃出注释
+pub fn comment_text(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_control() {
+                c.escape_default().to_string()
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 含各种特殊字符的文件名（最后一个不是合法的 UTF-8）
+    fn hostile_names() -> Vec<OsString> {
+        let mut names: Vec<OsString> = [
+            "plain.txt",
+            "it's.txt",
+            "say \"hi\".txt",
+            "$HOME $(id) ${x}.txt",
+            "`id`.txt",
+            "line\nbreak.txt",
+            "trailing\n",
+            "ctrl\x01char.txt",
+            "back\\slash.txt",
+            "\u{2018}curly\u{2019}.txt",
+            "-n",
+            "",
+        ]
+        .iter()
+        .map(OsString::from)
+        .collect();
+        names.push(os_from_bytes(b"bad\xffbyte.txt".to_vec()));
+        names
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn bash_quote_round_trips_through_bash() {
+        for name in hostile_names() {
+            let quoted = bash_quote(&name);
+            let output = std::process::Command::new("bash")
+                .arg("-c")
+                .arg(format!("printf %s {}", quoted))
+                .output()
+                .expect("需要 bash");
+            assert!(output.status.success(), "{}", quoted);
+            assert_eq!(output.stdout, os_bytes(&name).as_ref(), "{}", quoted);
+        }
+    }
+
+    #[test]
+    fn bash_quote_uses_ansi_c_only_when_needed() {
+        assert_eq!(bash_quote(OsStr::new("it's $HOME")), r"'it'\''s $HOME'");
+        assert_eq!(bash_quote(OsStr::new("a\nb")), r"$'a\x0ab'");
+        assert_eq!(bash_quote(OsStr::new("a\x01'\\")), r"$'a\x01\'\\'");
+        #[cfg(unix)]
+        assert_eq!(bash_quote(&os_from_bytes(b"a\xff".to_vec())), r"$'a\xff'");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn posix_quote_round_trips_through_sh() {
+        for name in hostile_names() {
+            let quoted = posix_quote(&name);
+            let output = std::process::Command::new("sh")
+                .arg("-c")
+                .arg(format!("printf %s {}", quoted))
+                .output()
+                .expect("需要 sh");
+            assert!(output.status.success(), "{}", quoted);
+            assert_eq!(output.stdout, os_bytes(&name).as_ref(), "{}", quoted);
+        }
+    }
+
+    #[test]
+    fn posix_quote_keeps_newlines_inside_single_quotes() {
+        // 命令替换会去掉末尾的换行，末尾的换行必须留在单引号内
+        assert_eq!(posix_quote(OsStr::new("a\n")), "'a\n'");
+        assert_eq!(posix_quote(OsStr::new("a\x01\n")), "'a'\"$(printf '\\001')\"'\n'");
+        assert_eq!(posix_quote(OsStr::new("")), "''");
+        #[cfg(unix)]
+        assert_eq!(posix_quote(&os_from_bytes(b"a\xff\xfe".to_vec())), "'a'\"$(printf '\\377\\376')\"");
+    }
+
+    #[test]
+    fn powershell_quote_doubles_all_single_quote_characters() {
+        assert_eq!(powershell_quote(OsStr::new("it's")), "'it''s'");
+        assert_eq!(powershell_quote(OsStr::new("\u{2018}x\u{2019}")), "'\u{2018}\u{2018}x\u{2019}\u{2019}'");
+        // 双引号、$ 和反引号在单引号内都是普通字符
+        assert_eq!(powershell_quote(OsStr::new("\"$x`\"")), "'\"$x`\"'");
+        #[cfg(unix)]
+        assert_eq!(powershell_quote(&os_from_bytes(b"a\xff".to_vec())), "'a\u{fffd}'");
+    }
+
+    #[test]
+    fn comment_text_escapes_control_characters() {
+        assert_eq!(comment_text("a\nrm -rf /"), "a\\nrm -rf /");
+        assert_eq!(comment_text("a\r\x01b"), "a\\r\\u{1}b");
+        assert_eq!(comment_text("\u{2018}ok\u{2019} $x"), "\u{2018}ok\u{2019} $x");
+        for name in hostile_names() {
+            assert!(!comment_text(&name.to_string_lossy()).contains('\n'));
+        }
+    }
+}
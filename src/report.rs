@@ -0,0 +1,177 @@
+// ============================================================================
+// JSON 报告：--json 导出的数据结构，以及根据（人工审阅过的）报告生成处理计划
+// `dupfinder apply 报告文件` 会重新校验每个文件，过期的记录一律拒绝处理
+// ============================================================================
+
+use crate::actions::GroupPlan;
+use crate::hasher::{self, HashAlgorithm};
+use crate::FileInfo;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateReport {
+    pub scan_info: ScanInfo,
+    pub duplicate_groups: Vec<DuplicateGroup>,
+    pub statistics: Statistics,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScanInfo {
+    pub base_path: String,
+    #[serde(default)]
+    pub roots: Vec<RootEntry>,
+    /// 旧版本的报告没有这个字段，使用的是 MD5
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+    #[serde(default)]
+    pub keep_policy: String,
+    pub total_groups: usize,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RootEntry {
+    pub label: String,
+    pub path: String,
+    pub reference: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    pub group_id: usize,
+    pub file_size: u64,
+    pub file_count: usize,
+    #[serde(alias = "md5_hash")]
+    pub hash: Option<String>,
+    pub files: Vec<FileEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileEntry {
+    pub path: String,
+    pub absolute_path: String,
+    #[serde(default)]
+    pub root: String,
+    /// 修改时间（RFC 3339，本地时区）
+    #[serde(default)]
+    pub modified: Option<String>,
+    #[serde(default)]
+    pub reference: bool,
+    /// 审阅时可以修改：true 表示保留，false 表示处理
+    #[serde(default)]
+    pub keep: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Statistics {
+    pub total_duplicate_files: usize,
+    pub deletable_files: usize,
+    pub potential_space_savings: u64,
+}
+
+/// 读取 JSON 报告
+pub fn load(path: &Path) -> io::Result<DuplicateReport> {
+    let content = fs::read_to_string(path)?;
+    serde_json::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// 确认文件仍然存在，且大小和哈希与报告一致
+fn validate(entry: &FileEntry, size: u64, hash: &str, algorithm: HashAlgorithm) -> Result<FileInfo, String> {
+    let path = PathBuf::from(&entry.absolute_path);
+    let metadata = fs::symlink_metadata(&path).map_err(|e| format!("无法读取: {}", e))?;
+    if !metadata.is_file() {
+        return Err("不再是普通文件".to_string());
+    }
+    if metadata.len() != size {
+        return Err(format!("大小已变化（报告 {} bytes，当前 {} bytes）", size, metadata.len()));
+    }
+    let current = hasher::hash_file(&path, algorithm).map_err(|e| format!("计算哈希失败: {}", e))?;
+    if current != hash {
+        return Err("哈希与报告不一致，文件已被修改".to_string());
+    }
+
+    let mut file = FileInfo::new(path, &metadata, 0, entry.reference);
+    file.full_hash = Some(current);
+    Ok(file)
+}
+
+/// 根据报告生成处理计划：`keep` 为 true 的文件和参考目录中的文件保留，其余文件处理。
+/// 每个文件都会重新校验，过期的记录不处理；保留的文件全部过期时整组跳过。
+/// 返回处理计划和被拒绝的记录数
+pub fn plan_from_report(report: &DuplicateReport) -> (Vec<GroupPlan>, usize) {
+    let algorithm = report.scan_info.hash_algorithm;
+    let mut plans = Vec::new();
+    let mut rejected = 0;
+
+    println!(
+        "{}",
+        format!(
+            "🔎 重新校验报告中的文件（{}，生成于 {}）",
+            algorithm.name(),
+            report.scan_info.timestamp
+        )
+        .cyan()
+    );
+
+    for group in &report.duplicate_groups {
+        let group_label = format!("组 {}:", group.group_id).bright_blue().bold();
+
+        let Some(ref hash) = group.hash else {
+            println!("{} {}", group_label, "报告中没有哈希，跳过".red());
+            rejected += group.files.len();
+            continue;
+        };
+
+        let mut keep = None;
+        let mut targets = Vec::new();
+        let mut stale = Vec::new();
+
+        for entry in &group.files {
+            let keep_entry = entry.keep || entry.reference;
+            match validate(entry, group.file_size, hash, algorithm) {
+                Ok(file) if keep_entry => {
+                    if keep.is_none() {
+                        keep = Some(file);
+                    }
+                }
+                Ok(file) => targets.push(file),
+                Err(reason) => stale.push((entry, keep_entry, reason)),
+            }
+        }
+
+        if let (true, Some(keep)) = (stale.is_empty(), &keep) {
+            if !targets.is_empty() {
+                plans.push(GroupPlan { keep: keep.clone(), targets });
+            }
+            continue;
+        }
+
+        // 只输出有问题的组
+        println!("{}", group_label);
+        for (entry, keep_entry, reason) in &stale {
+            let role = if *keep_entry { "保留的文件 " } else { "" };
+            println!("  {} 拒绝 {}{}: {}", "✗".red(), role, entry.absolute_path, reason);
+        }
+        rejected += stale.iter().filter(|(_, keep_entry, _)| !keep_entry).count();
+
+        match keep {
+            Some(keep) if !targets.is_empty() => plans.push(GroupPlan { keep, targets }),
+            Some(_) => {}
+            None => {
+                if !targets.is_empty() {
+                    println!(
+                        "  {}",
+                        format!("没有可用的保留文件（未标记 keep 或已过期），本组其余 {} 个文件不处理", targets.len()).red()
+                    );
+                }
+                rejected += targets.len();
+            }
+        }
+    }
+
+    (plans, rejected)
+}
@@ -0,0 +1,1145 @@
+// ============================================================================
+// 删除脚本：与运行的操作系统无关，可以在 Linux 上扫描共享目录、在 Windows 上执行
+// 支持 bash / POSIX sh / PowerShell / cmd / Python，路径可以按前缀映射
+// ============================================================================
+
+use crate::hasher::HashAlgorithm;
+use crate::{format_size, quote};
+use chrono::Local;
+use clap::ValueEnum;
+use std::ffi::{OsStr, OsString};
+use std::path::PathBuf;
+
+/// 删除脚本的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ScriptFormat {
+    /// Bash 脚本（Linux/macOS 上的默认格式）
+    Bash,
+    /// POSIX sh 脚本（dash、busybox 等没有 bash 的环境）
+    PosixSh,
+    /// PowerShell 脚本（Windows 上的默认格式）
+    Powershell,
+    /// Windows 批处理脚本（cmd.exe）
+    Cmd,
+    /// Python 3 脚本（跨平台）
+    Python,
+}
+
+impl ScriptFormat {
+    /// 运行时检测操作系统，决定默认生成哪种脚本
+    /// 使用 std::env::consts::OS 而不是编译时 cfg
+    pub fn host_default() -> Self {
+        if std::env::consts::OS == "windows" {
+            ScriptFormat::Powershell
+        } else {
+            ScriptFormat::Bash
+        }
+    }
+
+    /// 标题中显示的格式名称
+    fn title(self) -> &'static str {
+        match self {
+            ScriptFormat::Bash => "",
+            ScriptFormat::PosixSh => " (POSIX sh)",
+            ScriptFormat::Powershell => " (PowerShell)",
+            ScriptFormat::Cmd => " (cmd)",
+            ScriptFormat::Python => " (Python)",
+        }
+    }
+
+    /// 生成后是否需要设置可执行权限
+    pub fn executable(self) -> bool {
+        matches!(self, ScriptFormat::Bash | ScriptFormat::PosixSh | ScriptFormat::Python)
+    }
+
+    /// 执行方式提示
+    pub fn run_hint(self) -> &'static [&'static str] {
+        match self {
+            ScriptFormat::Bash => &["执行方式：bash <脚本文件>（加 --yes 跳过确认）"],
+            ScriptFormat::PosixSh => &["执行方式：sh <脚本文件>（加 --yes 跳过确认）"],
+            ScriptFormat::Powershell => &[
+                "执行方式：",
+                "  PowerShell -ExecutionPolicy Bypass -File <脚本文件>（加 -Yes 跳过确认）",
+                "  或右键脚本 -> 使用 PowerShell 运行",
+            ],
+            ScriptFormat::Cmd => &["执行方式：在 cmd 中运行 <脚本文件>（加 --yes 跳过确认），或双击运行"],
+            ScriptFormat::Python => &["执行方式：python3 <脚本文件>（Windows 上为 py <脚本文件>，加 --yes 跳过确认）"],
+        }
+    }
+}
+
+// ============================================================================
+// 路径映射
+// ============================================================================
+
+/// 路径前缀映射 FROM=TO：把扫描时的路径换成执行脚本的机器上的路径，
+/// 例如 /mnt/share=\\server\share。TO 中使用反斜杠时，其余部分的分隔符也换成反斜杠
+#[derive(Debug, Clone)]
+pub struct PathMap {
+    from: String,
+    to: String,
+    /// 分隔符在去掉末尾分隔符之前确定，D:\ 这样的 TO 仍然使用反斜杠
+    from_sep: Option<u8>,
+    to_sep: Option<u8>,
+}
+
+/// 解析 --script-path-map 参数
+pub fn parse_path_map(s: &str) -> Result<PathMap, String> {
+    let (from, to) = s
+        .split_once('=')
+        .ok_or_else(|| "格式应为 FROM=TO".to_string())?;
+    let (from_sep, to_sep) = (separator(from), separator(to));
+    let from = from.trim_end_matches(['/', '\\']);
+    if from.is_empty() || to.is_empty() {
+        return Err("FROM 和 TO 都不能为空".to_string());
+    }
+    Ok(PathMap {
+        from: from.to_string(),
+        to: to.trim_end_matches(['/', '\\']).to_string(),
+        from_sep,
+        to_sep,
+    })
+}
+
+fn separator(s: &str) -> Option<u8> {
+    if s.contains('\\') {
+        Some(b'\\')
+    } else if s.contains('/') {
+        Some(b'/')
+    } else {
+        None
+    }
+}
+
+/// 按顺序使用第一个匹配的映射（只在路径分隔符处匹配）
+pub fn map_path(maps: &[PathMap], path: &OsStr) -> OsString {
+    let bytes = quote::os_bytes(path);
+    for map in maps {
+        let Some(rest) = bytes.strip_prefix(map.from.as_bytes()) else {
+            continue;
+        };
+        let from_sep = map.from_sep.unwrap_or(b'/');
+        if !rest.is_empty() && rest[0] != from_sep {
+            continue;
+        }
+
+        let to_sep = map.to_sep.unwrap_or(from_sep);
+        let mut mapped = map.to.as_bytes().to_vec();
+        mapped.extend(rest.iter().map(|&b| if b == from_sep { to_sep } else { b }));
+        return quote::os_from_bytes(mapped);
+    }
+    path.to_os_string()
+}
+
+// ============================================================================
+// 脚本内容
+// ============================================================================
+
+/// 脚本中的一个文件（路径已经过映射）
+pub struct ScriptFile {
+    pub path: OsString,
+    pub size: u64,
+    /// 所属扫描目录的标签后缀，如 " [R1]"
+    pub label: String,
+}
+
+/// 脚本中的一组重复文件
+pub struct ScriptGroup {
+    pub size: u64,
+    pub file_count: usize,
+    /// 扫描时记录的哈希，脚本删除前用它校验文件内容
+    pub hash: Option<String>,
+    pub keep: ScriptFile,
+    /// 参考目录中的文件（始终保留）
+    pub references: Vec<ScriptFile>,
+    /// 要删除的文件
+    pub targets: Vec<ScriptFile>,
+}
+
+/// 生成脚本需要的全部信息
+pub struct ScriptInfo {
+    pub roots: String,
+    pub has_references: bool,
+    pub output_path: PathBuf,
+    pub hash_algorithm: HashAlgorithm,
+    pub groups: Vec<ScriptGroup>,
+}
+
+impl ScriptInfo {
+    fn deletable(&self) -> usize {
+        self.groups.iter().map(|g| g.targets.len()).sum()
+    }
+
+    fn savings(&self) -> u64 {
+        self.groups
+            .iter()
+            .flat_map(|g| &g.targets)
+            .map(|f| f.size)
+            .sum()
+    }
+
+    fn file_name(&self) -> String {
+        self.output_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// 脚本能否在删除前校验哈希：只支持各平台自带工具都能计算的 MD5 和 SHA-256
+    pub fn verifies(&self) -> bool {
+        matches!(self.hash_algorithm, HashAlgorithm::Md5 | HashAlgorithm::Sha256)
+            && self.groups.iter().all(|g| g.hash.is_some())
+    }
+}
+
+/// 生成指定格式的脚本
+pub fn render(format: ScriptFormat, info: &ScriptInfo) -> String {
+    match format {
+        ScriptFormat::Bash | ScriptFormat::PosixSh => render_sh(format, info),
+        ScriptFormat::Powershell => render_powershell(info),
+        ScriptFormat::Cmd => render_cmd(info),
+        ScriptFormat::Python => render_python(info),
+    }
+}
+
+const RULE: &str = "============================================================================";
+
+/// 脚本头部的说明（不含注释符号）
+fn header_lines(format: ScriptFormat, info: &ScriptInfo) -> Vec<String> {
+    let mut lines = vec![
+        RULE.to_string(),
+        format!("DupFinder 自动生成的删除脚本{}", format.title()),
+        format!("生成时间: {}", Local::now().format("%Y-%m-%d %H:%M:%S")),
+        format!("扫描路径: {}", info.roots),
+        format!("重复组数: {}", info.groups.len()),
+        RULE.to_string(),
+        String::new(),
+        "⚠️  警告：此脚本将删除重复文件！".to_string(),
+        "   每组重复文件会保留第一个，删除其他的。".to_string(),
+    ];
+    if info.has_references {
+        lines.push("   参考目录中的文件始终保留。".to_string());
+    }
+    if info.verifies() {
+        lines.push(format!(
+            "   删除前会校验 {} 哈希，保留的文件或要删除的文件在扫描后被修改时跳过。",
+            info.hash_algorithm.name()
+        ));
+    } else {
+        lines.push(format!(
+            "   脚本无法校验 {} 哈希，删除前只检查文件是否存在。",
+            info.hash_algorithm.name()
+        ));
+    }
+    lines.push("   请仔细检查后再执行！".to_string());
+    lines.push(String::new());
+    lines.push("使用方法:".to_string());
+    lines.push("  1. 仔细检查下面的删除命令".to_string());
+
+    let name = info.file_name();
+    match format {
+        ScriptFormat::Bash | ScriptFormat::PosixSh => {
+            lines.push("  2. 如果需要保留其他文件，请注释掉对应的删除代码块".to_string());
+            lines.push(format!("  3. 添加执行权限: chmod +x {}", info.output_path.display()));
+            lines.push(format!("  4. 执行脚本: ./{}", name));
+            lines.push(format!("  5. 无人值守执行（cron、CI）: ./{} --yes", name));
+        }
+        ScriptFormat::Powershell => {
+            lines.push("  2. 如果需要保留其他文件，请注释掉对应的删除代码块".to_string());
+            lines.push(format!("  3. 执行脚本: PowerShell -ExecutionPolicy Bypass -File {}", name));
+            lines.push("  4. 或右键 -> 使用 PowerShell 运行".to_string());
+            lines.push(format!("  5. 无人值守执行（计划任务）: PowerShell -ExecutionPolicy Bypass -File {} -Yes", name));
+        }
+        ScriptFormat::Cmd => {
+            lines.push("  2. 如果需要保留其他文件，请删除对应的 set/call 两行".to_string());
+            lines.push(format!("  3. 执行脚本: 在 cmd 中运行 {}，或双击运行", name));
+            lines.push(format!("  4. 无人值守执行（计划任务）: {} --yes", name));
+        }
+        ScriptFormat::Python => {
+            lines.push("  2. 如果需要保留其他文件，请注释掉 GROUPS 中对应的行".to_string());
+            lines.push(format!("  3. 执行脚本: python3 {}", name));
+            lines.push(format!("  4. 无人值守执行（cron、CI）: python3 {} --yes", name));
+        }
+    }
+    lines.push(RULE.to_string());
+    lines
+}
+
+/// 每组开头的说明（不含注释符号）
+fn group_lines(index: usize, group: &ScriptGroup) -> Vec<String> {
+    let mut lines = vec![
+        RULE.to_string(),
+        format!("组 {}: {} 个重复文件 (大小: {} bytes)", index + 1, group.file_count, group.size),
+        RULE.to_string(),
+        format!("保留: {}{}", group.keep.path.to_string_lossy(), group.keep.label),
+    ];
+    // 参考目录中的文件同样保留
+    for file in &group.references {
+        lines.push(format!("保留（参考目录）: {}{}", file.path.to_string_lossy(), file.label));
+    }
+    lines
+}
+
+/// 把说明写成注释，控制字符转义后不会逃出注释
+fn push_comments(script: &mut String, prefix: &str, lines: &[String]) {
+    for line in lines {
+        let text = quote::comment_text(line);
+        if text.is_empty() {
+            script.push_str(&format!("{}\n", prefix));
+        } else {
+            script.push_str(&format!("{} {}\n", prefix, text));
+        }
+    }
+}
+
+/// 把 (条件, 代码) 写成 if / elif / else 链，最后一项的条件为 None
+fn push_branches(script: &mut String, keywords: [&str; 4], branches: &[(Option<String>, String)]) {
+    let [if_kw, elif_kw, else_kw, end_kw] = keywords;
+    for (i, (condition, body)) in branches.iter().enumerate() {
+        match condition {
+            Some(condition) if i == 0 => script.push_str(&if_kw.replace("{}", condition)),
+            Some(condition) => script.push_str(&elif_kw.replace("{}", condition)),
+            None => script.push_str(else_kw),
+        }
+        script.push_str(body);
+    }
+    script.push_str(end_kw);
+}
+
+// ========================================================================
+// Bash / POSIX sh
+// ========================================================================
+fn render_sh(format: ScriptFormat, info: &ScriptInfo) -> String {
+    let quote_word = |path: &OsStr| match format {
+        ScriptFormat::Bash => quote::bash_quote(path),
+        _ => quote::posix_quote(path),
+    };
+    let verify = info.verifies();
+    let mut script = String::new();
+
+    // 脚本头部
+    script.push_str(if format == ScriptFormat::Bash { "#!/bin/bash\n" } else { "#!/bin/sh\n" });
+    push_comments(&mut script, "#", &header_lines(format, info));
+    script.push('\n');
+
+    // 安全检查
+    script.push_str("set -e  # 遇到错误立即退出\n");
+    script.push_str("set -u  # 使用未定义变量时报错\n\n");
+
+    // 命令行参数
+    script.push_str("# --yes / -y: 跳过确认提示（用于 cron、CI 等无人值守场景）\n");
+    script.push_str("assume_yes=0\n");
+    script.push_str("for arg in \"$@\"; do\n");
+    script.push_str("    case \"$arg\" in\n");
+    script.push_str("        -y|--yes) assume_yes=1 ;;\n");
+    script.push_str("    esac\n");
+    script.push_str("done\n\n");
+
+    // 哈希计算（从标准输入读取，输出中不会出现转义后的文件名）
+    if verify {
+        let (linux, fallback) = match info.hash_algorithm {
+            HashAlgorithm::Sha256 => ("sha256sum", "shasum -a 256"),
+            _ => ("md5sum", "md5 -r"),
+        };
+        script.push_str(&format!("# 计算文件的 {} 哈希（Linux 使用 {}，macOS 使用 {}）\n", info.hash_algorithm.name(), linux, fallback));
+        script.push_str("file_hash() {\n");
+        script.push_str(&format!("    if command -v {} >/dev/null 2>&1; then\n", linux));
+        script.push_str(&format!("        {} < \"$1\" | cut -d ' ' -f 1\n", linux));
+        script.push_str("    else\n");
+        script.push_str(&format!("        {} < \"$1\" | cut -d ' ' -f 1\n", fallback));
+        script.push_str("    fi\n");
+        script.push_str("}\n\n");
+    }
+
+    // 交互式确认
+    script.push_str("# 确认提示\n");
+    script.push_str("echo \"⚠️  警告: 即将删除重复文件！\"\n");
+    script.push_str(&format!("printf '扫描路径: %s\\n' {}\n", quote_word(OsStr::new(&info.roots))));
+    script.push_str(&format!("echo \"重复组数: {}\"\n", info.groups.len()));
+    script.push_str(&format!("echo \"将删除文件数: {}\"\n", info.deletable()));
+    script.push_str(&format!("echo \"可节省空间: {}\"\n", format_size(info.savings())));
+    script.push_str("echo \"\"\n");
+    script.push_str("if [ \"$assume_yes\" -ne 1 ]; then\n");
+    script.push_str("    printf '确认要继续吗? (yes/no): '\n");
+    script.push_str("    read -r confirm || confirm=\"\"\n");
+    script.push_str("    if [ \"$confirm\" != \"yes\" ]; then\n");
+    script.push_str("        echo \"❌ 已取消删除操作\"\n");
+    script.push_str("        exit 0\n");
+    script.push_str("    fi\n");
+    script.push_str("fi\n\n");
+
+    // 统计变量
+    script.push_str("# 统计变量\n");
+    script.push_str("deleted_count=0\n");
+    script.push_str("deleted_size=0\n");
+    script.push_str("failed_count=0\n");
+    if verify {
+        script.push_str("changed_count=0\n");
+    }
+    script.push('\n');
+
+    // 为每组生成删除命令
+    for (i, group) in info.groups.iter().enumerate() {
+        script.push('\n');
+        push_comments(&mut script, "#", &group_lines(i, group));
+
+        let hash = group.hash.as_deref().filter(|_| verify);
+        if let Some(hash) = hash {
+            let keep = quote_word(&group.keep.path);
+            script.push_str("# 保留的文件必须仍然存在且内容未变化，否则跳过本组\n");
+            script.push_str(&format!("if [ -f {} ] && [ \"$(file_hash {})\" = '{}' ]; then\n", keep, keep, hash));
+            script.push_str("    keep_ok=1\n");
+            script.push_str("else\n");
+            script.push_str("    keep_ok=0\n");
+            script.push_str(&format!("    printf '⚠️  保留的文件已变化或不存在，跳过本组: %s\\n' {}\n", keep));
+            script.push_str("fi\n");
+        }
+
+        for (j, file) in group.targets.iter().enumerate() {
+            let quoted = quote_word(&file.path);
+
+            let mut branches = Vec::new();
+            if let Some(hash) = hash {
+                branches.push((
+                    Some("[ \"$keep_ok\" -ne 1 ]".to_string()),
+                    "    changed_count=$((changed_count + 1))\n".to_string(),
+                ));
+                branches.push((
+                    Some(format!("[ ! -f {} ]", quoted)),
+                    format!("    printf '⚠️  文件不存在: %s\\n' {}\n", quoted),
+                ));
+                branches.push((
+                    Some(format!("[ \"$(file_hash {})\" != '{}' ]", quoted, hash)),
+                    format!(
+                        "    printf '⚠️  文件内容已变化，跳过: %s\\n' {}\n    changed_count=$((changed_count + 1))\n",
+                        quoted
+                    ),
+                ));
+            } else {
+                branches.push((
+                    Some(format!("[ ! -f {} ]", quoted)),
+                    format!("    printf '⚠️  文件不存在: %s\\n' {}\n", quoted),
+                ));
+            }
+
+            let mut delete = String::new();
+            delete.push_str(&format!("    printf '删除: %s\\n' {}\n", quoted));
+            delete.push_str(&format!("    if rm -- {}; then\n", quoted));
+            delete.push_str("        deleted_count=$((deleted_count + 1))\n");
+            delete.push_str(&format!("        deleted_size=$((deleted_size + {}))\n", file.size));
+            delete.push_str("    else\n");
+            delete.push_str(&format!("        printf '❌ 删除失败: %s\\n' {}\n", quoted));
+            delete.push_str("        failed_count=$((failed_count + 1))\n");
+            delete.push_str("    fi\n");
+            branches.push((None, delete));
+
+            script.push_str(&format!("\n# 删除文件 {}/{}{}\n", j + 1, group.targets.len(), file.label));
+            push_branches(&mut script, ["if {}; then\n", "elif {}; then\n", "else\n", "fi\n"], &branches);
+        }
+    }
+
+    // 脚本结尾 - 显示统计信息
+    script.push('\n');
+    push_comments(&mut script, "#", &[RULE.to_string(), "删除完成，显示统计信息".to_string(), RULE.to_string()]);
+    script.push_str("echo \"\"\n");
+    script.push_str("echo \"==============================================================================\"\n");
+    script.push_str("echo \"📊 删除统计:\"\n");
+    script.push_str("echo \"==============================================================================\"\n");
+    script.push_str("echo \"✅ 成功删除: $deleted_count 个文件\"\n");
+    script.push_str("echo \"❌ 失败数量: $failed_count 个文件\"\n");
+    if verify {
+        script.push_str("echo \"⏭️  内容已变化而跳过: $changed_count 个文件\"\n");
+    }
+    script.push_str("echo \"💾 节省空间: $(numfmt --to=iec-i --suffix=B $deleted_size 2>/dev/null || echo \"$deleted_size bytes\")\"\n");
+    script.push_str("echo \"==============================================================================\"\n");
+    // 有文件删除失败或因内容变化被跳过时退出码为 1，无人值守时可以据此报警
+    if verify {
+        script.push_str("if [ \"$failed_count\" -ne 0 ] || [ \"$changed_count\" -ne 0 ]; then\n");
+    } else {
+        script.push_str("if [ \"$failed_count\" -ne 0 ]; then\n");
+    }
+    script.push_str("    exit 1\n");
+    script.push_str("fi\n");
+
+    script
+}
+
+// ========================================================================
+// PowerShell
+// ========================================================================
+fn render_powershell(info: &ScriptInfo) -> String {
+    let verify = info.verifies();
+    // UTF-8 BOM：Windows PowerShell 5.1 把没有 BOM 的脚本当作 ANSI（cp1252）读取，
+    // 文件名中 UTF-8 的续字节 0x82/0x91/0x92 会变成 ‚ ‘ ’，被当作单引号结束字面量
+    let mut script = String::from("\u{feff}");
+
+    // 脚本头部
+    push_comments(&mut script, "#", &header_lines(ScriptFormat::Powershell, info));
+    script.push('\n');
+
+    // 命令行参数（param 必须是脚本的第一条语句）
+    script.push_str("param(\n");
+    script.push_str("    # 跳过确认提示（用于计划任务等无人值守场景）\n");
+    script.push_str("    [switch]$Yes\n");
+    script.push_str(")\n\n");
+
+    // 安全检查
+    script.push_str("# 设置错误处理\n");
+    script.push_str("$ErrorActionPreference = \"Stop\"\n\n");
+
+    if verify {
+        let algorithm = match info.hash_algorithm {
+            HashAlgorithm::Sha256 => "SHA256",
+            _ => "MD5",
+        };
+        script.push_str(&format!("# 计算文件的 {} 哈希，无法读取时返回空字符串\n", algorithm));
+        script.push_str("function Get-DupHash([string]$Path) {\n");
+        script.push_str("    try {\n");
+        script.push_str(&format!(
+            "        (Get-FileHash -LiteralPath $Path -Algorithm {}).Hash.ToLowerInvariant()\n",
+            algorithm
+        ));
+        script.push_str("    } catch {\n");
+        script.push_str("        ''\n");
+        script.push_str("    }\n");
+        script.push_str("}\n\n");
+    }
+
+    // 交互式确认
+    script.push_str("# 确认提示\n");
+    script.push_str("Write-Host \"⚠️  警告: 即将删除重复文件！\" -ForegroundColor Yellow\n");
+    script.push_str(&format!(
+        "Write-Host ('扫描路径: ' + {})\n",
+        quote::powershell_quote(OsStr::new(&info.roots))
+    ));
+    script.push_str(&format!("Write-Host \"重复组数: {}\"\n", info.groups.len()));
+    script.push_str(&format!("Write-Host \"将删除文件数: {}\"\n", info.deletable()));
+    script.push_str(&format!("Write-Host \"可节省空间: {}\"\n", format_size(info.savings())));
+    script.push_str("Write-Host \"\"\n");
+    script.push_str("if (-not $Yes) {\n");
+    script.push_str("    $confirm = Read-Host \"确认要继续吗? (yes/no)\"\n");
+    script.push_str("    if ($confirm -ne \"yes\") {\n");
+    script.push_str("        Write-Host \"❌ 已取消删除操作\" -ForegroundColor Red\n");
+    script.push_str("        exit 0\n");
+    script.push_str("    }\n");
+    script.push_str("}\n\n");
+
+    // 统计变量
+    script.push_str("# 统计变量\n");
+    script.push_str("$deletedCount = 0\n");
+    script.push_str("$deletedSize = 0\n");
+    script.push_str("$failedCount = 0\n");
+    if verify {
+        script.push_str("$changedCount = 0\n");
+    }
+    script.push('\n');
+
+    // 为每组生成删除命令
+    for (i, group) in info.groups.iter().enumerate() {
+        script.push('\n');
+        push_comments(&mut script, "#", &group_lines(i, group));
+
+        let hash = group.hash.as_deref().filter(|_| verify);
+        if let Some(hash) = hash {
+            let keep = quote::powershell_quote(&group.keep.path);
+            script.push_str("# 保留的文件必须仍然存在且内容未变化，否则跳过本组\n");
+            script.push_str(&format!(
+                "$keepOk = (Test-Path -LiteralPath {} -PathType Leaf) -and ((Get-DupHash {}) -eq '{}')\n",
+                keep, keep, hash
+            ));
+            script.push_str("if (-not $keepOk) {\n");
+            script.push_str(&format!(
+                "    Write-Host ('⚠️  保留的文件已变化或不存在，跳过本组: ' + {}) -ForegroundColor Yellow\n",
+                keep
+            ));
+            script.push_str("}\n");
+        }
+
+        for (j, file) in group.targets.iter().enumerate() {
+            let quoted = quote::powershell_quote(&file.path);
+            let missing = (
+                Some(format!("-not (Test-Path -LiteralPath {} -PathType Leaf)", quoted)),
+                format!("    Write-Host ('⚠️  文件不存在: ' + {}) -ForegroundColor Yellow\n", quoted),
+            );
+
+            let mut branches = Vec::new();
+            if let Some(hash) = hash {
+                branches.push((Some("-not $keepOk".to_string()), "    $changedCount++\n".to_string()));
+                branches.push(missing);
+                branches.push((
+                    Some(format!("(Get-DupHash {}) -ne '{}'", quoted, hash)),
+                    format!(
+                        "    Write-Host ('⚠️  文件内容已变化，跳过: ' + {}) -ForegroundColor Yellow\n    $changedCount++\n",
+                        quoted
+                    ),
+                ));
+            } else {
+                branches.push(missing);
+            }
+
+            let mut delete = String::new();
+            delete.push_str(&format!("    Write-Host ('删除: ' + {})\n", quoted));
+            delete.push_str("    try {\n");
+            delete.push_str(&format!("        Remove-Item -LiteralPath {} -Force\n", quoted));
+            delete.push_str("        $deletedCount++\n");
+            delete.push_str(&format!("        $deletedSize += {}\n", file.size));
+            delete.push_str("    } catch {\n");
+            delete.push_str(&format!("        Write-Host ('❌ 删除失败: ' + {}) -ForegroundColor Red\n", quoted));
+            delete.push_str("        $failedCount++\n");
+            delete.push_str("    }\n");
+            branches.push((None, delete));
+
+            script.push_str(&format!("\n# 删除文件 {}/{}{}\n", j + 1, group.targets.len(), file.label));
+            push_branches(&mut script, ["if ({}) {\n", "} elseif ({}) {\n", "} else {\n", "}\n"], &branches);
+        }
+    }
+
+    // 脚本结尾 - 显示统计信息
+    script.push('\n');
+    push_comments(&mut script, "#", &[RULE.to_string(), "删除完成，显示统计信息".to_string(), RULE.to_string()]);
+    script.push_str("Write-Host \"\"\n");
+    script.push_str("Write-Host \"==============================================================================\" -ForegroundColor Cyan\n");
+    script.push_str("Write-Host \"📊 删除统计:\" -ForegroundColor Cyan\n");
+    script.push_str("Write-Host \"==============================================================================\" -ForegroundColor Cyan\n");
+    script.push_str("Write-Host \"✅ 成功删除: $deletedCount 个文件\" -ForegroundColor Green\n");
+    script.push_str("Write-Host \"❌ 失败数量: $failedCount 个文件\" -ForegroundColor Red\n");
+    if verify {
+        script.push_str("Write-Host \"⏭️  内容已变化而跳过: $changedCount 个文件\" -ForegroundColor Yellow\n");
+    }
+    script.push_str("$sizeInMB = [math]::Round($deletedSize / 1MB, 2)\n");
+    script.push_str("if ($sizeInMB -gt 0) {\n");
+    script.push_str("    Write-Host \"💾 节省空间: $sizeInMB MB ($deletedSize bytes)\" -ForegroundColor Green\n");
+    script.push_str("} else {\n");
+    script.push_str("    Write-Host \"💾 节省空间: $deletedSize bytes\" -ForegroundColor Green\n");
+    script.push_str("}\n");
+    script.push_str("Write-Host \"==============================================================================\" -ForegroundColor Cyan\n");
+    script.push_str("\n# 暂停，等待用户按键（无人值守时不暂停）\n");
+    script.push_str("if (-not $Yes) {\n");
+    script.push_str("    Write-Host \"\"\n");
+    script.push_str("    Write-Host \"按任意键退出...\" -ForegroundColor Gray\n");
+    script.push_str("    $null = $Host.UI.RawUI.ReadKey(\"NoEcho,IncludeKeyDown\")\n");
+    script.push_str("}\n");
+    if verify {
+        script.push_str("if ($failedCount -gt 0 -or $changedCount -gt 0) { exit 1 }\n");
+    } else {
+        script.push_str("if ($failedCount -gt 0) { exit 1 }\n");
+    }
+
+    script
+}
+
+// ========================================================================
+// cmd 批处理
+// ========================================================================
+/// 批处理中括号代码块内的 %变量% 在整个代码块解析时就已展开，
+/// 而路径中的 ! 要求关闭延迟展开，所以每个文件的处理都放在子程序中：
+/// 先 set "target=路径"，再 call :delete_target 哈希
+fn render_cmd(info: &ScriptInfo) -> String {
+    let verify = info.verifies();
+    let mut script = String::new();
+
+    // UTF-8 代码页，关闭延迟变量展开（路径中的 ! 才不会被解释）
+    script.push_str("@echo off\n");
+    script.push_str("chcp 65001 >nul\n");
+    script.push_str("setlocal EnableExtensions DisableDelayedExpansion\n");
+    // REM 行中的 % 同样会被展开
+    let header: Vec<String> = header_lines(ScriptFormat::Cmd, info)
+        .iter()
+        .map(|line| line.replace('%', "%%"))
+        .collect();
+    push_comments(&mut script, "REM", &header);
+    script.push('\n');
+
+    // 命令行参数
+    script.push_str("REM --yes 或 /y: 跳过确认提示（用于计划任务等无人值守场景）\n");
+    script.push_str("set \"assume_yes=\"\n");
+    script.push_str("if /i \"%~1\"==\"--yes\" set \"assume_yes=1\"\n");
+    script.push_str("if /i \"%~1\"==\"/y\" set \"assume_yes=1\"\n\n");
+
+    // 交互式确认
+    let roots = quote::cmd_quote(OsStr::new(&info.roots))
+        .unwrap_or_else(|| format!("\"{}\"", info.roots.replace('"', "'").replace('%', "%%")));
+    script.push_str("echo ⚠️  警告: 即将删除重复文件！\n");
+    script.push_str(&format!("echo 扫描路径: {}\n", roots));
+    script.push_str(&format!("echo 重复组数: {}\n", info.groups.len()));
+    script.push_str(&format!("echo 将删除文件数: {}\n", info.deletable()));
+    script.push_str(&format!("echo 可节省空间: {}\n", format_size(info.savings())));
+    script.push_str("echo.\n");
+    script.push_str("if defined assume_yes goto :confirmed\n");
+    script.push_str("set \"confirm=\"\n");
+    script.push_str("set /p \"confirm=确认要继续吗? (yes/no): \"\n");
+    script.push_str("if not \"%confirm%\"==\"yes\" (\n");
+    script.push_str("    echo ❌ 已取消删除操作\n");
+    script.push_str("    exit /b 0\n");
+    script.push_str(")\n");
+    script.push_str(":confirmed\n\n");
+
+    // 统计变量（set /a 只支持 32 位整数，不统计字节数）
+    script.push_str("set deleted_count=0\n");
+    script.push_str("set failed_count=0\n");
+    script.push_str("set skipped_count=0\n");
+    if verify {
+        script.push_str("set changed_count=0\n");
+    }
+    script.push('\n');
+
+    for (i, group) in info.groups.iter().enumerate() {
+        script.push('\n');
+        let lines: Vec<String> = group_lines(i, group).iter().map(|l| l.replace('%', "%%")).collect();
+        push_comments(&mut script, "REM", &lines);
+
+        let hash = group.hash.as_deref().filter(|_| verify);
+        if let Some(hash) = hash {
+            match quote::cmd_quote(&group.keep.path) {
+                Some(keep) => {
+                    script.push_str(&format!("set {}\n", set_value("keep", &keep)));
+                    script.push_str(&format!("call :check_keep {}\n", hash));
+                }
+                None => {
+                    script.push_str("REM 保留的文件无法在 cmd 中安全表示，本组不处理\n");
+                    script.push_str("set \"keep_ok=\"\n");
+                }
+            }
+        }
+
+        for (j, file) in group.targets.iter().enumerate() {
+            script.push_str(&format!(
+                "REM 删除文件 {}/{}{}\n",
+                j + 1,
+                group.targets.len(),
+                file.label.replace('%', "%%")
+            ));
+
+            let Some(quoted) = quote::cmd_quote(&file.path) else {
+                // 含双引号或控制字符的路径无法在批处理中安全表示
+                let shown = quote::comment_text(&file.path.to_string_lossy()).replace('%', "%%");
+                script.push_str(&format!("REM 无法在 cmd 中安全表示，已跳过: {}\n", shown));
+                script.push_str("echo ⚠️  跳过一个无法在 cmd 中表示的路径\n");
+                script.push_str("set /a skipped_count+=1\n");
+                continue;
+            };
+
+            script.push_str(&format!("set {}\n", set_value("target", &quoted)));
+            match hash {
+                Some(hash) => script.push_str(&format!("call :delete_target {}\n", hash)),
+                None => script.push_str("call :delete_target\n"),
+            }
+        }
+    }
+
+    // 脚本结尾 - 显示统计信息
+    script.push('\n');
+    push_comments(&mut script, "REM", &[RULE.to_string(), "删除完成，显示统计信息".to_string(), RULE.to_string()]);
+    script.push_str("echo.\n");
+    script.push_str("echo ==============================================================================\n");
+    script.push_str("echo 📊 删除统计:\n");
+    script.push_str("echo ==============================================================================\n");
+    script.push_str("echo ✅ 成功删除: %deleted_count% 个文件\n");
+    script.push_str("echo ❌ 失败数量: %failed_count% 个文件\n");
+    if verify {
+        script.push_str("echo ⏭️  内容已变化而跳过: %changed_count% 个文件\n");
+    }
+    script.push_str("if not %skipped_count%==0 echo ⚠️  跳过数量: %skipped_count% 个文件\n");
+    script.push_str("echo ==============================================================================\n");
+    script.push_str("if not defined assume_yes pause\n");
+    script.push_str("if not %failed_count%==0 exit /b 1\n");
+    if verify {
+        script.push_str("if not %changed_count%==0 exit /b 1\n");
+    }
+    script.push_str("exit /b 0\n\n");
+
+    // 子程序
+    push_comments(&mut script, "REM", &[RULE.to_string(), "子程序".to_string(), RULE.to_string()]);
+    if verify {
+        let algorithm = match info.hash_algorithm {
+            HashAlgorithm::Sha256 => "SHA256",
+            _ => "MD5",
+        };
+        script.push_str(":file_hash\n");
+        script.push_str(&format!(
+            "REM 计算 hash_path 的 {} 哈希，结果保存在 file_hash 中（certutil 输出中只有哈希这一行不含冒号）\n",
+            algorithm
+        ));
+        script.push_str("set \"file_hash=\"\n");
+        script.push_str(&format!(
+            "for /f \"delims=\" %%h in ('certutil -hashfile \"%hash_path%\" {} 2^>nul ^| findstr /v \":\"') do if not defined file_hash set \"file_hash=%%h\"\n",
+            algorithm
+        ));
+        // 旧版 Windows 的输出中每个字节之间有空格
+        script.push_str("if defined file_hash set \"file_hash=%file_hash: =%\"\n");
+        script.push_str("exit /b 0\n\n");
+
+        script.push_str(":check_keep\n");
+        script.push_str("REM 保留的文件必须仍然存在且内容未变化，否则跳过本组\n");
+        script.push_str("set \"keep_ok=\"\n");
+        script.push_str("set \"hash_path=%keep%\"\n");
+        script.push_str("call :file_hash\n");
+        script.push_str("if /i \"%file_hash%\"==\"%~1\" set \"keep_ok=1\"\n");
+        script.push_str("if not defined keep_ok echo ⚠️  保留的文件已变化或不存在，跳过本组: \"%keep%\"\n");
+        script.push_str("exit /b 0\n\n");
+    }
+
+    script.push_str(":delete_target\n");
+    if verify {
+        script.push_str("if not defined keep_ok (\n");
+        script.push_str("    set /a changed_count+=1\n");
+        script.push_str("    exit /b 0\n");
+        script.push_str(")\n");
+    }
+    script.push_str("if not exist \"%target%\" (\n");
+    script.push_str("    echo ⚠️  文件不存在: \"%target%\"\n");
+    script.push_str("    exit /b 0\n");
+    script.push_str(")\n");
+    if verify {
+        script.push_str("set \"hash_path=%target%\"\n");
+        script.push_str("call :file_hash\n");
+        script.push_str("if /i not \"%file_hash%\"==\"%~1\" (\n");
+        script.push_str("    echo ⚠️  文件内容已变化，跳过: \"%target%\"\n");
+        script.push_str("    set /a changed_count+=1\n");
+        script.push_str("    exit /b 0\n");
+        script.push_str(")\n");
+    }
+    // del 失败时不一定设置 errorlevel，删除后再检查文件是否还在
+    script.push_str("del /f /q \"%target%\" 2>nul\n");
+    script.push_str("if exist \"%target%\" (\n");
+    script.push_str("    echo ❌ 删除失败: \"%target%\"\n");
+    script.push_str("    set /a failed_count+=1\n");
+    script.push_str(") else (\n");
+    script.push_str("    echo 删除: \"%target%\"\n");
+    script.push_str("    set /a deleted_count+=1\n");
+    script.push_str(")\n");
+    script.push_str("exit /b 0\n");
+
+    // 批处理文件使用 CRLF 换行
+    script.replace('\n', "\r\n")
+}
+
+/// set "name=值"：quoted 是 cmd_quote 的结果，去掉外层引号后放进 set 的引号中
+fn set_value(name: &str, quoted: &str) -> String {
+    format!("\"{}={}", name, &quoted[1..])
+}
+
+// ========================================================================
+// Python
+// ========================================================================
+fn render_python(info: &ScriptInfo) -> String {
+    let verify = info.verifies();
+    let mut script = String::new();
+
+    // 脚本头部
+    script.push_str("#!/usr/bin/env python3\n");
+    script.push_str("# -*- coding: utf-8 -*-\n");
+    push_comments(&mut script, "#", &header_lines(ScriptFormat::Python, info));
+    script.push('\n');
+    script.push_str("import hashlib\n");
+    script.push_str("import os\n");
+    script.push_str("import sys\n\n");
+    script.push_str(&format!(
+        "ROOTS = {}\n",
+        quote::python_literal(OsStr::new(&info.roots))
+    ));
+    if verify {
+        script.push_str(&format!("# 删除前校验的哈希算法（None 表示不校验）\nHASH_ALGORITHM = \"{}\"\n\n", info.hash_algorithm.name()));
+    } else {
+        script.push_str("# 删除前校验的哈希算法（None 表示不校验）\nHASH_ALGORITHM = None\n\n");
+    }
+
+    // 每组：(保留的文件, 扫描时的哈希, [(要删除的文件, 大小), ...])
+    script.push_str("# 每组：(保留的文件, 扫描时的哈希, [(要删除的文件, 大小), ...])\n");
+    script.push_str("GROUPS = [\n");
+    for (i, group) in info.groups.iter().enumerate() {
+        let lines = group_lines(i, group);
+        let mut comments = String::new();
+        push_comments(&mut comments, "    #", &lines);
+        script.push_str(&comments);
+
+        let hash = match group.hash.as_deref().filter(|_| verify) {
+            Some(hash) => format!("\"{}\"", hash),
+            None => "None".to_string(),
+        };
+        script.push_str(&format!("    ({}, {}, [\n", quote::python_literal(&group.keep.path), hash));
+        for file in &group.targets {
+            script.push_str(&format!("        ({}, {}),", quote::python_literal(&file.path), file.size));
+            if !file.label.is_empty() {
+                script.push_str(&format!("  #{}", quote::comment_text(&file.label)));
+            }
+            script.push('\n');
+        }
+        script.push_str("    ]),\n");
+    }
+    script.push_str("]\n\n\n");
+
+    script.push_str(
+        r#"def show(path):
+    """用于输出的路径（无法解码的字节显示为替换字符）"""
+    return path.encode("utf-8", "surrogateescape").decode("utf-8", "replace")
+
+
+def file_hash(path):
+    """计算文件哈希，无法读取时返回 None"""
+    h = hashlib.new(HASH_ALGORITHM)
+    try:
+        with open(path, "rb") as f:
+            for chunk in iter(lambda: f.read(1 << 20), b""):
+                h.update(chunk)
+    except OSError:
+        return None
+    return h.hexdigest()
+
+
+def main():
+    if hasattr(sys.stdout, "reconfigure"):
+        sys.stdout.reconfigure(errors="replace")
+    # --yes / -y: 跳过确认提示（用于 cron、CI 等无人值守场景）
+    assume_yes = any(arg in ("--yes", "-y") for arg in sys.argv[1:])
+
+    # 确认提示
+    print("⚠️  警告: 即将删除重复文件！")
+    print("扫描路径: " + ROOTS)
+    print("重复组数: {}".format(len(GROUPS)))
+    print("将删除文件数: {}".format(sum(len(files) for _, _, files in GROUPS)))
+"#,
+    );
+    script.push_str(&format!("    print(\"可节省空间: {}\")\n", format_size(info.savings())));
+    script.push_str(
+        r#"    print()
+    if not assume_yes:
+        try:
+            confirm = input("确认要继续吗? (yes/no): ")
+        except EOFError:
+            confirm = ""
+        if confirm != "yes":
+            print("❌ 已取消删除操作")
+            return 0
+
+    deleted_count = 0
+    deleted_size = 0
+    failed_count = 0
+    changed_count = 0
+
+    for keep, expected, files in GROUPS:
+        # 保留的文件必须仍然存在且内容未变化，否则跳过本组
+        if expected is not None and file_hash(keep) != expected:
+            print("⚠️  保留的文件已变化或不存在，跳过本组: " + show(keep))
+            changed_count += len(files)
+            continue
+
+        for path, size in files:
+            if not os.path.isfile(path):
+                print("⚠️  文件不存在: " + show(path))
+                continue
+            if expected is not None and file_hash(path) != expected:
+                print("⚠️  文件内容已变化，跳过: " + show(path))
+                changed_count += 1
+                continue
+            try:
+                os.remove(path)
+            except OSError as e:
+                print("❌ 删除失败: {} ({})".format(show(path), e))
+                failed_count += 1
+            else:
+                print("删除: " + show(path))
+                deleted_count += 1
+                deleted_size += size
+
+    # 删除完成，显示统计信息
+    print()
+    print("=" * 78)
+    print("📊 删除统计:")
+    print("=" * 78)
+    print("✅ 成功删除: {} 个文件".format(deleted_count))
+    print("❌ 失败数量: {} 个文件".format(failed_count))
+    if HASH_ALGORITHM:
+        print("⏭️  内容已变化而跳过: {} 个文件".format(changed_count))
+    print("💾 节省空间: {} bytes".format(deleted_size))
+    print("=" * 78)
+    return 1 if failed_count or changed_count else 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
+"#,
+    );
+
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process::Command;
+
+    fn script_file(path: OsString) -> ScriptFile {
+        ScriptFile { path, size: 4, label: " [R1]".to_string() }
+    }
+
+    /// 一组文件名含引号、$、反引号、换行、控制字符和非 UTF-8 字节的重复文件
+    fn hostile_info(output_path: PathBuf) -> ScriptInfo {
+        let targets: Vec<ScriptFile> = [
+            "/data/it's.txt",
+            "/data/say \"hi\".txt",
+            "/data/$(id) ${HOME}.txt",
+            "/data/`id`.txt",
+            "/data/line\nbreak.txt",
+            "/data/trailing\n",
+            "/data/ctrl\x01char.txt",
+            "/data/back\\slash\\",
+            "/data/\u{2018}curly\u{2019}.txt",
+        ]
+        .iter()
+        .map(|name| script_file(OsString::from(name)))
+        .chain([script_file(quote::os_from_bytes(b"/data/bad\xffbyte.txt".to_vec()))])
+        .collect();
+        ScriptInfo {
+            roots: "/data\n$(id)".to_string(),
+            has_references: true,
+            output_path,
+            hash_algorithm: HashAlgorithm::Sha256,
+            groups: vec![ScriptGroup {
+                size: 4,
+                file_count: targets.len() + 2,
+                hash: Some("9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08".to_string()),
+                keep: script_file(OsString::from("/data/keep 'me'.txt")),
+                references: vec![script_file(OsString::from("/ref/$x\n.txt"))],
+                targets,
+            }],
+        }
+    }
+
+    /// 生成脚本并用 `program args... <脚本>` 检查语法
+    fn check_syntax(format: ScriptFormat, file_name: &str, program: &str, args: &[&str]) {
+        let dir = crate::test_dir(&format!("script-syntax-{}", file_name));
+        let path = dir.join(file_name);
+        fs::write(&path, render(format, &hostile_info(path.clone()))).unwrap();
+
+        let output = Command::new(program)
+            .args(args)
+            .arg(&path)
+            .output()
+            .unwrap_or_else(|e| panic!("需要 {}: {}", program, e));
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn bash_script_with_hostile_names_parses() {
+        check_syntax(ScriptFormat::Bash, "delete.sh", "bash", &["-n"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn posix_sh_script_with_hostile_names_parses() {
+        check_syntax(ScriptFormat::PosixSh, "delete-posix.sh", "sh", &["-n"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn python_script_with_hostile_names_compiles() {
+        check_syntax(ScriptFormat::Python, "delete.py", "python3", &["-m", "py_compile"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn bash_script_with_yes_skips_modified_target() {
+        let dir = crate::test_dir("script-modified-target");
+        for name in ["keep", "same", "changed"] {
+            fs::write(dir.join(name), "data").unwrap();
+        }
+        let hash = crate::hasher::hash_file(&dir.join("keep"), HashAlgorithm::Sha256).unwrap();
+        let file = |name: &str| script_file(dir.join(name).into_os_string());
+        let script_path = dir.join("delete.sh");
+        let info = ScriptInfo {
+            roots: dir.display().to_string(),
+            has_references: false,
+            output_path: script_path.clone(),
+            hash_algorithm: HashAlgorithm::Sha256,
+            groups: vec![ScriptGroup {
+                size: 4,
+                file_count: 3,
+                hash: Some(hash),
+                keep: file("keep"),
+                references: Vec::new(),
+                targets: vec![file("same"), file("changed")],
+            }],
+        };
+        fs::write(&script_path, render(ScriptFormat::Bash, &info)).unwrap();
+
+        // 扫描后修改了一个目标文件（大小不变）
+        fs::write(dir.join("changed"), "DATA").unwrap();
+        let status = Command::new("bash").arg(&script_path).arg("--yes").status().expect("需要 bash");
+
+        assert_eq!(status.code(), Some(1));
+        assert!(!dir.join("same").exists());
+        assert_eq!(fs::read_to_string(dir.join("changed")).unwrap(), "DATA");
+        assert!(dir.join("keep").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn powershell_script_starts_with_utf8_bom() {
+        // т、ё、ђ 的 UTF-8 编码分别以 0x82、0x91、0x92 结尾，在 cp1252 中是 ‚ ‘ ’
+        let mut info = hostile_info(PathBuf::from("delete.ps1"));
+        let names = ["/data/т'; Remove-Item C:\\ #.txt", "/data/ё.txt", "/data/ђ.txt"];
+        info.groups[0].targets = names.iter().map(|name| script_file(OsString::from(name))).collect();
+        let script = render(ScriptFormat::Powershell, &info);
+
+        assert!(script.as_bytes().starts_with(b"\xef\xbb\xbf"));
+        assert!(script.contains("param("));
+        for name in names {
+            let quoted = quote::powershell_quote(OsStr::new(name));
+            assert!(script.contains(&quoted), "{}", quoted);
+        }
+        assert!(script.contains("'/data/т''; Remove-Item C:\\ #.txt'"));
+        // 这些字节确实出现在脚本中，只有按 BOM 以 UTF-8 读取时才不会被当作引号
+        for continuation in [[0xd1, 0x82], [0xd1, 0x91], [0xd1, 0x92]] {
+            assert!(script.as_bytes().windows(2).any(|w| w == continuation));
+        }
+    }
+
+    #[test]
+    fn parse_path_map_rejects_invalid_maps() {
+        assert!(parse_path_map("/mnt/share").is_err());
+        assert!(parse_path_map("=/x").is_err());
+        assert!(parse_path_map("/=/x").is_err());
+        assert!(parse_path_map("/x=").is_err());
+    }
+
+    #[test]
+    fn map_path_converts_to_unc_path() {
+        let maps = [parse_path_map(r"/mnt/share=\\server\share").unwrap()];
+        assert_eq!(map_path(&maps, OsStr::new("/mnt/share/photos/a b.jpg")), r"\\server\share\photos\a b.jpg");
+        assert_eq!(map_path(&maps, OsStr::new("/mnt/share")), r"\\server\share");
+        // 只在路径分隔符处匹配
+        assert_eq!(map_path(&maps, OsStr::new("/mnt/shared/a.jpg")), "/mnt/shared/a.jpg");
+        assert_eq!(map_path(&maps, OsStr::new("/other/a.jpg")), "/other/a.jpg");
+    }
+
+    #[test]
+    fn map_path_uses_first_matching_map_and_ignores_trailing_separators() {
+        let maps = [
+            parse_path_map("/data/photos/=/srv/photos/").unwrap(),
+            parse_path_map("/data=D:\\").unwrap(),
+        ];
+        assert_eq!(map_path(&maps, OsStr::new("/data/photos/x.jpg")), "/srv/photos/x.jpg");
+        assert_eq!(map_path(&maps, OsStr::new("/data/docs/y.txt")), r"D:\docs\y.txt");
+
+        // Windows 路径映射到 Unix 路径
+        let maps = [parse_path_map(r"C:\Users\me=/home/me").unwrap()];
+        assert_eq!(map_path(&maps, OsStr::new(r"C:\Users\me\a\b.txt")), "/home/me/a/b.txt");
+        let maps = [parse_path_map(r"C:\=/mnt/c/").unwrap()];
+        assert_eq!(map_path(&maps, OsStr::new(r"C:\a\b.txt")), "/mnt/c/a/b.txt");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn map_path_keeps_non_utf8_bytes() {
+        let maps = [parse_path_map("/mnt/share=/srv").unwrap()];
+        let path = quote::os_from_bytes(b"/mnt/share/bad\xff.txt".to_vec());
+        assert_eq!(map_path(&maps, &path), quote::os_from_bytes(b"/srv/bad\xff.txt".to_vec()));
+    }
+}
@@ -0,0 +1,240 @@
+// ============================================================================
+// 回收站：按照 freedesktop.org Trash 规范把文件移到回收站
+// 与主目录同一文件系统的文件放入 $XDG_DATA_HOME/Trash，
+// 其他文件系统的文件放入所在挂载点的 .Trash/$uid（共享回收站可用时）或 .Trash-$uid，
+// 都可以在文件管理器中恢复
+// ============================================================================
+
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 已移到回收站的文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedFile {
+    /// 文件在回收站 files/ 目录中的位置
+    pub files_path: PathBuf,
+    /// 对应的 .trashinfo 文件
+    pub info_path: PathBuf,
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod imp {
+    use super::TrashedFile;
+    use chrono::Local;
+    use std::ffi::OsString;
+    use std::fs::{self, DirBuilder, OpenOptions};
+    use std::io::{self, Write};
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+    use std::path::{Path, PathBuf};
+
+    /// 主目录回收站：$XDG_DATA_HOME/Trash，默认 ~/.local/share/Trash
+    fn home_trash() -> Option<PathBuf> {
+        std::env::var_os("XDG_DATA_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+            })
+            .map(|data| data.join("Trash"))
+    }
+
+    /// 创建回收站目录（权限 0700）
+    fn create_trash_dirs(trash: &Path) -> io::Result<()> {
+        let mut builder = DirBuilder::new();
+        builder.recursive(true).mode(0o700);
+        builder.create(trash.join("files"))?;
+        builder.create(trash.join("info"))
+    }
+
+    /// 文件所在文件系统的挂载点（向上查找，直到设备号变化）
+    fn mount_point(path: &Path, dev: u64) -> PathBuf {
+        let mut dir = path.parent().unwrap_or(Path::new("/")).to_path_buf();
+        while let Some(parent) = dir.parent() {
+            match fs::metadata(parent) {
+                Ok(metadata) if metadata.dev() == dev => dir = parent.to_path_buf(),
+                _ => break,
+            }
+        }
+        dir
+    }
+
+    /// 选择回收站目录，返回 (回收站目录, 挂载点)；
+    /// 使用主目录回收站时挂载点为 None，.trashinfo 中记录绝对路径
+    fn select_trash(path: &Path, dev: u64) -> io::Result<(PathBuf, Option<PathBuf>)> {
+        if let Some(home) = home_trash() {
+            create_trash_dirs(&home)?;
+            if fs::metadata(&home)?.dev() == dev {
+                return Ok((home, None));
+            }
+        }
+
+        // SAFETY: getuid 没有任何前置条件，且总是成功
+        let uid = unsafe { libc::getuid() };
+        let topdir = mount_point(path, dev);
+        let trash = topdir_trash(&topdir, uid)?;
+        Ok((trash, Some(topdir)))
+    }
+
+    /// 挂载点上的回收站：优先使用管理员创建的共享回收站 $topdir/.Trash/$uid，
+    /// 共享回收站必须是设置了粘滞位的目录且不是符号链接，否则按规范不使用；
+    /// 不可用或无法在其中创建目录时改用 $topdir/.Trash-$uid
+    fn topdir_trash(topdir: &Path, uid: libc::uid_t) -> io::Result<PathBuf> {
+        let is_symlink = |path: &Path| path.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink());
+
+        let shared = topdir.join(".Trash");
+        if shared
+            .symlink_metadata()
+            .is_ok_and(|m| m.is_dir() && m.mode() & 0o1000 != 0)
+        {
+            let trash = shared.join(uid.to_string());
+            if !is_symlink(&trash) && create_trash_dirs(&trash).is_ok() {
+                return Ok(trash);
+            }
+        }
+
+        let trash = topdir.join(format!(".Trash-{}", uid));
+        if is_symlink(&trash) {
+            return Err(io::Error::other(format!("回收站目录是符号链接，拒绝使用: {}", trash.display())));
+        }
+        create_trash_dirs(&trash)?;
+        Ok(trash)
+    }
+
+    /// 按规范对 .trashinfo 中的路径进行 URL 编码（保留 '/'）
+    fn encode_path(path: &Path) -> String {
+        let mut encoded = String::new();
+        for &byte in path.as_os_str().as_bytes() {
+            if byte.is_ascii_alphanumeric() || b"-_.~/".contains(&byte) {
+                encoded.push(byte as char);
+            } else {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        }
+        encoded
+    }
+
+    /// 第 n 个候选文件名：name、name.2.ext、name.3.ext ...
+    fn candidate_name(path: &Path, n: usize) -> OsString {
+        let name = path.file_name().unwrap_or_default().to_os_string();
+        if n == 1 {
+            return name;
+        }
+        let stem = path.file_stem().unwrap_or_default();
+        let mut candidate = stem.to_os_string();
+        candidate.push(format!(".{}", n));
+        if let Some(ext) = path.extension() {
+            candidate.push(".");
+            candidate.push(ext);
+        }
+        candidate
+    }
+
+    pub fn trash_file(path: &Path) -> io::Result<TrashedFile> {
+        let path = std::path::absolute(path)?;
+        let dev = fs::symlink_metadata(&path)?.dev();
+        let (trash, topdir) = select_trash(&path, dev)?;
+
+        let recorded_path = match topdir {
+            Some(ref topdir) => path.strip_prefix(topdir).unwrap_or(&path).to_path_buf(),
+            None => path.clone(),
+        };
+        let info = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            encode_path(&recorded_path),
+            Local::now().format("%Y-%m-%dT%H:%M:%S")
+        );
+
+        let mut n = 0;
+        loop {
+            n += 1;
+            let name = candidate_name(&path, n);
+            let files_path = trash.join("files").join(&name);
+            let mut info_name = name.clone();
+            info_name.push(".trashinfo");
+            let info_path = trash.join("info").join(info_name);
+
+            // 先以独占方式创建 .trashinfo 占住名字，再移动文件
+            let mut info_file = match OpenOptions::new().write(true).create_new(true).open(&info_path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            };
+            if files_path.symlink_metadata().is_ok() {
+                let _ = fs::remove_file(&info_path);
+                continue;
+            }
+
+            let result = info_file
+                .write_all(info.as_bytes())
+                .and_then(|_| info_file.sync_all())
+                .and_then(|_| fs::rename(&path, &files_path));
+            if let Err(e) = result {
+                let _ = fs::remove_file(&info_path);
+                return Err(e);
+            }
+
+            return Ok(TrashedFile { files_path, info_path });
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::os::unix::fs::PermissionsExt;
+
+        #[test]
+        fn topdir_trash_prefers_sticky_shared_trash() {
+            let topdir = crate::test_dir("trash-sticky");
+            fs::create_dir(topdir.join(".Trash")).unwrap();
+            fs::set_permissions(topdir.join(".Trash"), fs::Permissions::from_mode(0o1777)).unwrap();
+
+            assert_eq!(topdir_trash(&topdir, 1234).unwrap(), topdir.join(".Trash/1234"));
+            assert!(topdir.join(".Trash/1234/files").is_dir());
+            assert!(topdir.join(".Trash/1234/info").is_dir());
+
+            fs::remove_dir_all(&topdir).unwrap();
+        }
+
+        #[test]
+        fn topdir_trash_ignores_shared_trash_without_sticky_bit() {
+            let topdir = crate::test_dir("trash-not-sticky");
+            fs::create_dir(topdir.join(".Trash")).unwrap();
+            fs::set_permissions(topdir.join(".Trash"), fs::Permissions::from_mode(0o777)).unwrap();
+
+            assert_eq!(topdir_trash(&topdir, 1234).unwrap(), topdir.join(".Trash-1234"));
+            assert!(!topdir.join(".Trash/1234").exists());
+
+            fs::remove_dir_all(&topdir).unwrap();
+        }
+
+        #[test]
+        fn topdir_trash_ignores_symlinked_shared_trash() {
+            let topdir = crate::test_dir("trash-symlink");
+            let elsewhere = topdir.join("elsewhere");
+            fs::create_dir(&elsewhere).unwrap();
+            fs::set_permissions(&elsewhere, fs::Permissions::from_mode(0o1777)).unwrap();
+            std::os::unix::fs::symlink(&elsewhere, topdir.join(".Trash")).unwrap();
+
+            assert_eq!(topdir_trash(&topdir, 1234).unwrap(), topdir.join(".Trash-1234"));
+            assert!(!elsewhere.join("1234").exists());
+
+            fs::remove_dir_all(&topdir).unwrap();
+        }
+    }
+}
+
+/// 把文件移到回收站
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn trash_file(path: &Path) -> io::Result<TrashedFile> {
+    imp::trash_file(path)
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+pub fn trash_file(_path: &Path) -> io::Result<TrashedFile> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "回收站功能仅支持遵循 freedesktop.org 规范的 Linux/BSD 桌面",
+    ))
+}
@@ -0,0 +1,453 @@
+// ============================================================================
+// 终端界面：全屏浏览重复文件组，标记要处理的文件后统一执行
+// 左侧是按浪费空间排序的组列表，右侧是组内文件和文件详情
+// ============================================================================
+
+use crate::actions::{Action, GroupPlan, LinkKind};
+use crate::{format_size, removable_files, selected_action, DupFinder, FileInfo, ScanArgs};
+use chrono::{DateTime, Local};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::io;
+
+/// 一组重复文件及其标记状态
+struct GroupView {
+    files: Vec<FileInfo>,
+    /// 删除组内可删除文件能节省的空间
+    wasted: u64,
+    /// 与 files 一一对应，true 表示已标记为待处理
+    marked: Vec<bool>,
+}
+
+impl GroupView {
+    fn marked_count(&self) -> usize {
+        self.marked.iter().filter(|&&m| m).count()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Groups,
+    Files,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Normal,
+    /// 正在输入过滤条件
+    Filter,
+    /// 确认执行操作
+    Confirm,
+}
+
+struct App<'a> {
+    finder: &'a DupFinder,
+    groups: Vec<GroupView>,
+    /// 通过过滤条件的组（groups 的下标）
+    visible: Vec<usize>,
+    group_state: ListState,
+    file_state: ListState,
+    focus: Focus,
+    mode: Mode,
+    filter: String,
+    default_action: Action,
+    relative_symlinks: bool,
+    status: String,
+}
+
+impl<'a> App<'a> {
+    fn new(finder: &'a DupFinder, args: &ScanArgs, duplicates: Vec<Vec<FileInfo>>) -> Self {
+        let mut groups: Vec<GroupView> = duplicates
+            .into_iter()
+            .map(|files| {
+                let wasted = files[0].size * removable_files(&files).count() as u64;
+                let marked = vec![false; files.len()];
+                GroupView { files, wasted, marked }
+            })
+            .collect();
+        // 浪费空间最多的组排在最前面（稳定排序，相同时保持扫描顺序）
+        groups.sort_by_key(|group| std::cmp::Reverse(group.wasted));
+
+        let mut app = App {
+            finder,
+            groups,
+            visible: Vec::new(),
+            group_state: ListState::default(),
+            file_state: ListState::default(),
+            focus: Focus::Groups,
+            mode: Mode::Normal,
+            filter: String::new(),
+            default_action: selected_action(args).unwrap_or(Action::Delete),
+            relative_symlinks: args.relative_symlinks,
+            status: String::new(),
+        };
+        app.apply_filter();
+        app
+    }
+
+    /// 按路径子串（不区分大小写）过滤组
+    fn apply_filter(&mut self) {
+        let needle = self.filter.to_lowercase();
+        self.visible = (0..self.groups.len())
+            .filter(|&i| {
+                needle.is_empty()
+                    || self.groups[i]
+                        .files
+                        .iter()
+                        .any(|file| self.finder.format_path(file).to_lowercase().contains(&needle))
+            })
+            .collect();
+        self.group_state
+            .select(if self.visible.is_empty() { None } else { Some(0) });
+        self.file_state.select(Some(0));
+    }
+
+    fn current_group(&self) -> Option<usize> {
+        self.group_state.selected().and_then(|i| self.visible.get(i).copied())
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let (state, len) = match self.focus {
+            Focus::Groups => (&mut self.group_state, self.visible.len()),
+            Focus::Files => {
+                let len = self
+                    .current_group()
+                    .map(|g| self.groups[g].files.len())
+                    .unwrap_or(0);
+                (&mut self.file_state, len)
+            }
+        };
+        if len == 0 {
+            return;
+        }
+        let current = state.selected().unwrap_or(0) as isize;
+        let next = (current + delta).clamp(0, len as isize - 1) as usize;
+        state.select(Some(next));
+        if self.focus == Focus::Groups {
+            self.file_state.select(Some(0));
+        }
+    }
+
+    /// 切换当前文件的标记；参考文件不能标记，每组至少保留一个文件
+    fn toggle_mark(&mut self) {
+        let (Some(g), Some(f)) = (self.current_group(), self.file_state.selected()) else {
+            return;
+        };
+        let group = &mut self.groups[g];
+        if group.files[f].is_reference {
+            self.status = "参考目录中的文件始终保留，不能标记".to_string();
+        } else if !group.marked[f] && group.marked_count() + 1 == group.files.len() {
+            self.status = "每组至少需要保留一个文件".to_string();
+        } else {
+            group.marked[f] = !group.marked[f];
+            self.status.clear();
+        }
+    }
+
+    /// 按默认规则标记当前组：保留第一个文件，标记其余可删除的文件
+    fn auto_mark(&mut self) {
+        if let Some(g) = self.current_group() {
+            let group = &mut self.groups[g];
+            for (j, file) in group.files.iter().enumerate() {
+                group.marked[j] = j > 0 && !file.is_reference;
+            }
+        }
+    }
+
+    fn unmark_group(&mut self) {
+        if let Some(g) = self.current_group() {
+            self.groups[g].marked.fill(false);
+        }
+    }
+
+    /// 已标记的文件数和大小
+    fn marked_totals(&self) -> (usize, u64) {
+        self.groups.iter().fold((0, 0), |(count, bytes), group| {
+            let n = group.marked_count();
+            (count + n, bytes + n as u64 * group.files[0].size)
+        })
+    }
+
+    /// 根据标记生成处理计划：保留组内第一个未标记的文件，处理已标记的文件
+    fn plans(&self) -> Vec<GroupPlan> {
+        self.groups
+            .iter()
+            .filter(|group| group.marked_count() > 0)
+            .filter_map(|group| {
+                let keep = group.marked.iter().position(|&m| !m)?;
+                let targets = group
+                    .files
+                    .iter()
+                    .zip(&group.marked)
+                    .filter(|(_, &m)| m)
+                    .map(|(file, _)| file.clone())
+                    .collect();
+                Some(GroupPlan {
+                    keep: group.files[keep].clone(),
+                    targets,
+                })
+            })
+            .collect()
+    }
+
+    fn link(&self, kind: LinkKind) -> Action {
+        Action::Link {
+            kind,
+            relative: self.relative_symlinks,
+        }
+    }
+}
+
+/// 运行终端界面；用户确认执行时返回处理计划和选择的操作，退出时返回 None
+pub fn run(
+    finder: &DupFinder,
+    args: &ScanArgs,
+    duplicates: Vec<Vec<FileInfo>>,
+) -> io::Result<Option<(Vec<GroupPlan>, Action)>> {
+    let mut app = App::new(finder, args, duplicates);
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> io::Result<Option<(Vec<GroupPlan>, Action)>> {
+    loop {
+        terminal.draw(|frame| draw(frame, app))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        match app.mode {
+            Mode::Filter => match key.code {
+                KeyCode::Enter => app.mode = Mode::Normal,
+                KeyCode::Esc => {
+                    app.filter.clear();
+                    app.apply_filter();
+                    app.mode = Mode::Normal;
+                }
+                KeyCode::Backspace => {
+                    app.filter.pop();
+                    app.apply_filter();
+                }
+                KeyCode::Char(c) => {
+                    app.filter.push(c);
+                    app.apply_filter();
+                }
+                _ => {}
+            },
+            Mode::Confirm => {
+                let action = match key.code {
+                    KeyCode::Enter | KeyCode::Char('y') => Some(app.default_action.clone()),
+                    KeyCode::Char('d') => Some(Action::Delete),
+                    KeyCode::Char('h') => Some(app.link(LinkKind::Hard)),
+                    KeyCode::Char('r') => Some(app.link(LinkKind::Reflink)),
+                    KeyCode::Char('s') => Some(app.link(LinkKind::Symbolic)),
+                    _ => None,
+                };
+                match action {
+                    Some(action) => return Ok(Some((app.plans(), action))),
+                    None => app.mode = Mode::Normal,
+                }
+            }
+            Mode::Normal => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+                KeyCode::Up | KeyCode::Char('k') => app.move_selection(-1),
+                KeyCode::Down | KeyCode::Char('j') => app.move_selection(1),
+                KeyCode::PageUp => app.move_selection(-10),
+                KeyCode::PageDown => app.move_selection(10),
+                KeyCode::Tab | KeyCode::Left | KeyCode::Right | KeyCode::Char('h') | KeyCode::Char('l') => {
+                    app.focus = match app.focus {
+                        Focus::Groups => Focus::Files,
+                        Focus::Files => Focus::Groups,
+                    };
+                }
+                KeyCode::Enter => app.focus = Focus::Files,
+                KeyCode::Char(' ') if app.focus == Focus::Files => app.toggle_mark(),
+                KeyCode::Char('a') => app.auto_mark(),
+                KeyCode::Char('u') => app.unmark_group(),
+                KeyCode::Char('/') => app.mode = Mode::Filter,
+                KeyCode::Char('x') => {
+                    if app.marked_totals().0 == 0 {
+                        app.status = "还没有标记任何文件".to_string();
+                    } else {
+                        app.mode = Mode::Confirm;
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+}
+
+fn format_mtime(file: &FileInfo) -> String {
+    file.modified
+        .map(|t| DateTime::<Local>::from(t).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn pane_block(title: &str, focused: bool) -> Block<'_> {
+    let style = if focused {
+        Style::default().fg(Color::Cyan)
+    } else {
+        Style::default()
+    };
+    Block::default().borders(Borders::ALL).border_style(style).title(title)
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let [header, body, footer] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [left, right] = Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)]).areas(body);
+    let [files_area, detail_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(7)]).areas(right);
+
+    // 顶部：统计和过滤条件
+    let (marked, marked_bytes) = app.marked_totals();
+    let mut title = format!(
+        " DupFinder  {} 组  已标记 {} 个文件 ({})",
+        app.visible.len(),
+        marked,
+        format_size(marked_bytes)
+    );
+    if !app.filter.is_empty() || app.mode == Mode::Filter {
+        title.push_str(&format!("  过滤: {}", app.filter));
+    }
+    frame.render_widget(
+        Paragraph::new(title).style(Style::default().fg(Color::Black).bg(Color::Cyan)),
+        header,
+    );
+
+    // 左侧：组列表
+    let items: Vec<ListItem> = app
+        .visible
+        .iter()
+        .map(|&g| {
+            let group = &app.groups[g];
+            let mut spans = vec![
+                Span::styled(format!("{:>10}", format_size(group.wasted)), Style::default().fg(Color::Yellow)),
+                Span::raw(format!("  {} × {}", group.files.len(), format_size(group.files[0].size))),
+            ];
+            let n = group.marked_count();
+            if n > 0 {
+                spans.push(Span::styled(format!("  [{}]", n), Style::default().fg(Color::Red)));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(pane_block("重复文件组（按浪费空间排序）", app.focus == Focus::Groups))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, left, &mut app.group_state);
+
+    // 右侧：组内文件和详情
+    let current = app.current_group();
+    let items: Vec<ListItem> = current
+        .map(|g| {
+            let group = &app.groups[g];
+            group
+                .files
+                .iter()
+                .zip(&group.marked)
+                .map(|(file, &marked)| {
+                    let (mark, style) = if marked {
+                        ("[x] ", Style::default().fg(Color::Red))
+                    } else if file.is_reference {
+                        ("[R] ", Style::default().fg(Color::DarkGray))
+                    } else {
+                        ("[ ] ", Style::default().fg(Color::Green))
+                    };
+                    ListItem::new(Line::from(vec![
+                        Span::styled(mark, style),
+                        Span::raw(app.finder.format_path(file)),
+                    ]))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let list = List::new(items)
+        .block(pane_block("文件（空格标记/取消）", app.focus == Focus::Files))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, files_area, &mut app.file_state);
+
+    let detail = current
+        .zip(app.file_state.selected())
+        .and_then(|(g, f)| app.groups[g].files.get(f))
+        .map(|file| {
+            let mut lines = vec![
+                Line::from(format!("路径: {}", file.path.display())),
+                Line::from(format!("大小: {} ({} bytes)", format_size(file.size), file.size)),
+                Line::from(format!("修改时间: {}", format_mtime(file))),
+            ];
+            let mut root = app.finder.roots[file.root].path.display().to_string();
+            if file.is_reference {
+                root.push_str("（参考，始终保留）");
+            }
+            lines.push(Line::from(format!("扫描目录: {}", root)));
+            if let Some(ref hash) = file.full_hash {
+                lines.push(Line::from(format!("{}: {}", app.finder.hash_algorithm.name(), hash)));
+            }
+            lines
+        })
+        .unwrap_or_default();
+    frame.render_widget(
+        Paragraph::new(detail).block(Block::default().borders(Borders::ALL).title("详情")),
+        detail_area,
+    );
+
+    // 底部：按键提示或状态信息
+    let help = match app.mode {
+        Mode::Normal if !app.status.is_empty() => app.status.clone(),
+        Mode::Normal => "↑↓ 移动  Tab 切换  空格 标记  a 自动标记  u 取消本组  / 过滤  x 执行  q 退出".to_string(),
+        Mode::Filter => "输入路径关键字过滤，Enter 确认，Esc 清除".to_string(),
+        Mode::Confirm => String::new(),
+    };
+    frame.render_widget(Paragraph::new(help).style(Style::default().fg(Color::DarkGray)), footer);
+
+    if app.mode == Mode::Confirm {
+        draw_confirm(frame, app, marked, marked_bytes);
+    }
+}
+
+/// 执行前的确认对话框
+fn draw_confirm(frame: &mut Frame, app: &App, marked: usize, marked_bytes: u64) {
+    let area = frame.area();
+    let width = 56.min(area.width);
+    let height = 9.min(area.height);
+    let popup = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+
+    let lines = vec![
+        Line::from(format!("处理 {} 个已标记的文件（{}）", marked, format_size(marked_bytes))),
+        Line::from(""),
+        Line::from(format!("Enter  {}", app.default_action.describe())),
+        Line::from("d      删除"),
+        Line::from("h / r / s  硬链接 / reflink / 符号链接"),
+        Line::from("其他键  取消"),
+    ];
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow))
+                .title("确认执行"),
+        ),
+        popup,
+    );
+}